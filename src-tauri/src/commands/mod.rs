@@ -1,16 +1,34 @@
 // Tauri command handlers
 // These functions are called from the frontend
 
-use crate::rpc::{VerusRpcClient, RpcCredentials, ChainConfig, ChainDiscovery, CredentialManager, CurrencyDefinition};
+use crate::rpc::{VerusRpcClient, RpcCredentials, ChainConfig, ChainDiscovery, CredentialManager, DefineCurrencyRequest};
+use crate::rpc::vdxf::{self, ContentMultimapBuilder, ContentMultimapInput};
+use crate::rpc::amount::Amount;
+use crate::rpc::subid_registration::{SubIdCommitment, SubIdRegistrationRequest};
+use crate::rpc::identity_name::{self, IdentityRef};
+use crate::rpc::currency_options::{CurrencyOptions, CurrencyOptionsInfo};
+use crate::rpc::launch_tracker::{LaunchTracker, LAUNCH_EVENT};
+use crate::rpc::offer_monitor::{OfferMonitor, OfferMonitorConfig};
+use crate::rpc::offer_builder::{MakeOfferRequest, TakeOfferRequest};
+use crate::rpc::offers::MarketOffer;
+use crate::rpc::wallet_history::HistoryFilter;
+use crate::rpc::history_export::ExportRequest;
+use crate::rpc::fee_estimator::FeeEstimateRequest;
+use crate::rpc::transfer_tracker::CrossChainTransfer;
+use crate::rpc::send_builder::{self, SendOutput};
+use crate::rpc::protected_conversion::{ProtectedConversion, ProtectedConversionRequest};
 use serde_json::{json, Value};
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 // Application state
 pub struct AppState {
-    pub active_client: Arc<RwLock<Option<VerusRpcClient>>>,
+    pub active_client: Arc<RwLock<Option<Arc<VerusRpcClient>>>>,
     pub discovered_chains: Arc<RwLock<Vec<ChainConfig>>>,
+    pub launch_tracker: Arc<LaunchTracker>,
+    pub offer_monitor: Arc<OfferMonitor>,
 }
 
 impl Default for AppState {
@@ -18,6 +36,8 @@ impl Default for AppState {
         Self {
             active_client: Arc::new(RwLock::new(None)),
             discovered_chains: Arc::new(RwLock::new(Vec::new())),
+            launch_tracker: Arc::new(LaunchTracker::default()),
+            offer_monitor: Arc::new(OfferMonitor::default()),
         }
     }
 }
@@ -117,6 +137,8 @@ pub async fn get_identity(name: String, chain: Option<String>, state: State<'_,
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
 
+    let name = IdentityRef::parse(&name).map_err(|e| e.to_string())?.to_rpc_param();
+
     // Use the RPC client method to handle chain parameters properly
     client.get_identity(&name, None, None, None, chain.as_deref())
         .await
@@ -133,6 +155,8 @@ pub async fn get_identity_content(
     chain: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    let name = IdentityRef::parse(&name).map_err(|e| e.to_string())?.to_rpc_param();
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
 
@@ -148,6 +172,62 @@ pub async fn get_identity_content(
     .map_err(|e| e.to_string())
 }
 
+/// Parse and validate an identity name or i-address without calling the daemon
+#[tauri::command]
+pub async fn parse_identity_name(name: String) -> Result<Value, String> {
+    IdentityRef::parse(&name)
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Resolve a friendly name to its i-address or an i-address to its fully qualified name
+#[tauri::command]
+pub async fn resolve_identity(
+    name_or_address: String,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.resolve_identity(&name_or_address, chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Chronological list of identity revisions with per-revision diffs
+#[tauri::command]
+pub async fn get_identity_timeline(
+    name: String,
+    heightstart: Option<u64>,
+    heightend: Option<u64>,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let name = IdentityRef::parse(&name).map_err(|e| e.to_string())?.to_rpc_param();
+
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_identity_timeline(&name, heightstart, heightend, chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Report wallet identities with unsafe revocation/recovery or timelock configuration
+#[tauri::command]
+pub async fn audit_identity_authorities(chain: Option<String>, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.audit_identity_authorities(chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn verify_message(
     taddr_or_identity: String,
@@ -194,6 +274,51 @@ pub async fn verify_hash(
     .map_err(|e| e.to_string())
 }
 
+// VDXF Commands
+#[tauri::command]
+pub async fn get_vdxf_id(
+    vdxf_uri: String,
+    initial_data: Option<Value>,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_vdxf_id(&vdxf_uri, initial_data, chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Build a contentmultimap for `updateidentity`, resolving qualified names to i-addresses
+#[tauri::command]
+pub async fn build_content_multimap(
+    entries: Vec<ContentMultimapInput>,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    let builder = entries.iter().fold(ContentMultimapBuilder::new(), |builder, entry| {
+        builder.add(&entry.key, entry.to_descriptor())
+    });
+
+    builder.build(client, chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Decode the DataDescriptor entries of a contentmultimap returned by the daemon
+#[tauri::command]
+pub async fn decode_content_multimap(contentmultimap: Value) -> Result<Value, String> {
+    vdxf::decode_content_multimap(&contentmultimap)
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn list_currencies(
     query: Option<String>,
@@ -233,23 +358,16 @@ pub async fn list_currencies(
         }
     }
     
-    let mut params = Vec::new();
-    
     // If we have query object parameters, use them; otherwise fall back to legacy query string
-    if has_query_params {
-        params.push(serde_json::json!(query_obj));
-        if let Some(v) = verbose {
-            params.push(serde_json::json!(v));
-        }
-    } else if let Some(q) = query {
-        params.push(serde_json::json!(q));
-        if let Some(v) = verbose {
-            params.push(serde_json::json!(v));
-        }
-    }
+    let query = if has_query_params {
+        Some(serde_json::json!(query_obj))
+    } else {
+        query.map(|q| serde_json::json!(q))
+    };
     
-    client.call("listcurrencies", serde_json::json!(params))
+    client.list_currencies(query, verbose)
         .await
+        .map(|r| serde_json::to_value(r).unwrap())
         .map_err(|e| e.to_string())
 }
 
@@ -262,18 +380,9 @@ pub async fn get_currency(
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
-    // Call the RPC method directly without trying to deserialize into a struct
-    // since the actual response structure varies
-    let mut params = vec![];
-    
-    params.push(serde_json::json!(currency_name));
-    
-    if let Some(h) = height {
-        params.push(serde_json::json!(h));
-    }
-    
-    client.call("getcurrency", serde_json::json!(params))
+    client.get_currency(&currency_name, height)
         .await
+        .map(|r| serde_json::to_value(r).unwrap())
         .map_err(|e| e.to_string())
 }
 
@@ -310,6 +419,58 @@ pub async fn get_currency_state(
         .map_err(|e| e.to_string())
 }
 
+/// Sample a currency's state over a height range as chart series, reusing cached history
+#[tauri::command]
+pub async fn get_currency_series(
+    currency_name: String,
+    start: u64,
+    end: Option<u64>,
+    step: u64,
+    conversion_currency: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_currency_series(&currency_name, start, end, step, conversion_currency.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_block_header(height: u64, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_block_header(height)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Size and per-method breakdown of the active chain's on-disk cache
+#[tauri::command]
+pub async fn get_chain_cache_info(state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_chain_cache_info()
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Clear the active chain's cache, or only one RPC method's entries
+#[tauri::command]
+pub async fn clear_chain_cache(method: Option<String>, state: State<'_, AppState>) -> Result<usize, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.clear_chain_cache(method.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_offers(
     currency_or_id: String, 
@@ -335,6 +496,103 @@ pub async fn list_open_offers(state: State<'_, AppState>) -> Result<Value, Strin
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_market_offers(
+    currency_or_id: String,
+    is_currency: bool,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_market_offers(&currency_or_id, is_currency)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Offers for a currency or identity as a book of bids and asks in one quote currency
+#[tauri::command]
+pub async fn get_order_book(
+    currency_or_id: String,
+    is_currency: bool,
+    quote_currency: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_order_book(&currency_or_id, is_currency, quote_currency.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_wallet_offers(state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.list_wallet_offers()
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Watch the wallet's open offers in the background, emitting events as they near and pass expiry
+///
+/// Calling it again while running only replaces the configuration.
+#[tauri::command]
+pub async fn start_offer_monitor(
+    interval_secs: Option<u64>,
+    config: Option<OfferMonitorConfig>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let monitor = state.offer_monitor.clone();
+    if let Some(config) = config {
+        monitor.configure(config).map_err(|e| e.to_string())?;
+    }
+    let Some(generation) = monitor.start() else {
+        return Ok(());
+    };
+
+    let active_client = state.active_client.clone();
+    let interval = Duration::from_secs(interval_secs.unwrap_or(60).max(10));
+
+    tauri::async_runtime::spawn(async move {
+        while monitor.is_current(generation) {
+            // Release the lock before polling, which may close offers, so a reconnect is not held up
+            let client = active_client.read().await.clone();
+            let events = match client {
+                Some(client) => client.poll_offer_expiry(&monitor).await,
+                None => Ok(Vec::new()),
+            };
+
+            match events {
+                Ok(events) => {
+                    for event in &events {
+                        if let Err(e) = app.emit(event.notice.event(), event) {
+                            eprintln!("Failed to emit offer event: {}", e);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Offer monitor poll failed: {}", e),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_offer_monitor(state: State<'_, AppState>) -> Result<(), String> {
+    state.offer_monitor.stop();
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn close_offers(
     offer_tx_ids: Vec<String>,
@@ -382,6 +640,71 @@ pub async fn take_offer(
         .map_err(|e| e.to_string())
 }
 
+/// Check a typed offer against the wallet's holdings and the chain tip without submitting it
+#[tauri::command]
+pub async fn validate_make_offer(request: MakeOfferRequest, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.validate_make_offer(&request)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn make_offer_checked(
+    request: MakeOfferRequest,
+    return_tx: Option<bool>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.make_offer_checked(&request, return_tx)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn validate_take_offer(request: TakeOfferRequest, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.validate_take_offer(&request)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn take_offer_checked(
+    request: TakeOfferRequest,
+    return_tx: Option<bool>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.take_offer_checked(&request, return_tx)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Re-check an offer against the chain right before taking it
+#[tauri::command]
+pub async fn verify_offer(offer: MarketOffer, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.verify_offer(&offer)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 // Chain Discovery Commands
 #[tauri::command]
 pub async fn discover_chains(state: State<'_, AppState>) -> Result<Value, String> {
@@ -440,7 +763,7 @@ pub async fn connect_to_chain(
         Ok(_) => {
             // Store the working client
             let mut active_client = state.active_client.write().await;
-            *active_client = Some(client);
+            *active_client = Some(Arc::new(client));
             Ok(true)
         }
         Err(e) => Err(format!("Failed to connect to chain: {}", e))
@@ -497,7 +820,7 @@ pub async fn test_and_connect_manual(
 
     // Store the working client in app state
     let mut active_client = state.active_client.write().await;
-    *active_client = Some(client);
+    *active_client = Some(Arc::new(client));
 
     // Return connection info
     Ok(json!({
@@ -594,7 +917,7 @@ pub async fn list_address_groupings(chain: Option<String>, state: State<'_, AppS
 #[tauri::command]
 pub async fn estimate_conversion(
     currency: String,
-    amount: f64,
+    amount: Amount,
     convertto: String,
     via: Option<String>,
     state: State<'_, AppState>
@@ -612,11 +935,28 @@ pub async fn estimate_conversion(
     .map_err(|e| e.to_string())
 }
 
+/// Ranked direct and via-basket routes from one currency to another
+#[tauri::command]
+pub async fn find_conversion_routes(
+    source: String,
+    destination: String,
+    amount: Amount,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.find_conversion_routes(&source, &destination, amount)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn convert_currency(
     from_address: String,
     source_currency: String,
-    amount: f64,
+    amount: Amount,
     target_currency: String,
     to_address: String,
     state: State<'_, AppState>
@@ -630,12 +970,42 @@ pub async fn convert_currency(
         .map_err(|e| e.to_string())
 }
 
+/// Convert only if a fresh estimate is within the request's slippage limit
+#[tauri::command]
+pub async fn convert_currency_protected(
+    request: ProtectedConversionRequest,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.convert_currency_protected(&request)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Compare the settled output of a protected conversion with its estimate
+#[tauri::command]
+pub async fn check_conversion_settlement(
+    conversion: ProtectedConversion,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.check_conversion_settlement(&conversion)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 // Simplified conversion specifically for VerusIDX funding
 #[tauri::command]
 pub async fn convert_to_verusidx(
     from_address: String,
     source_currency: String,
-    amount: f64,
+    amount: Amount,
     to_address: String,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
@@ -655,7 +1025,7 @@ pub async fn send_currency(
     from_address: String,
     outputs: Value,
     min_conf: Option<u32>,
-    fee_amount: Option<f64>,
+    fee_amount: Option<Amount>,
     subtract_fee_from_amount: Option<bool>,
     state: State<'_, AppState>
 ) -> Result<String, String> {
@@ -671,16 +1041,70 @@ pub async fn send_currency(
         .map_err(|e| e.to_string())
 }
 
+/// `send_currency` with outputs described by kind, e.g. `{"kind": "convert", ...}`
+#[tauri::command]
+pub async fn send_currency_outputs(
+    from_address: String,
+    outputs: Vec<SendOutput>,
+    min_conf: Option<u32>,
+    fee_amount: Option<Amount>,
+    state: State<'_, AppState>
+) -> Result<String, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    let outputs = outputs.iter().map(SendOutput::to_request).collect();
+    client.send_currency(&from_address, outputs, min_conf, fee_amount, None)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Network, conversion and export fees a send would pay, without sending anything
+#[tauri::command]
+pub async fn estimate_send_fees(request: FeeEstimateRequest, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.estimate_send_fees(&request)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Per-output errors for a `send_currency` call, without sending anything
+#[tauri::command]
+pub async fn validate_send_outputs(outputs: Value) -> Result<Value, String> {
+    let outputs: Vec<crate::rpc::SendCurrencyRequest> = serde_json::from_value(outputs)
+        .map_err(|e| format!("Invalid outputs format: {}", e))?;
+
+    Ok(serde_json::to_value(send_builder::validate_send_outputs(&outputs)).unwrap())
+}
+
+/// Stage and ETA of a cross-chain send, checking the import on the destination's daemon when one is discovered
+#[tauri::command]
+pub async fn track_cross_chain_transfer(transfer: CrossChainTransfer, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+    let chains = state.discovered_chains.read().await.clone();
+
+    client.track_cross_chain_transfer(&transfer, &chains)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 // Identity management commands
 #[tauri::command]
 pub async fn revoke_identity(
     name_or_id: String,
     return_tx: Option<bool>,
     token_revoke: Option<bool>,
-    fee_offer: Option<f64>,
+    fee_offer: Option<Amount>,
     source_of_funds: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    let name_or_id = IdentityRef::parse(&name_or_id).map_err(|e| e.to_string())?.to_rpc_param();
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
@@ -700,10 +1124,12 @@ pub async fn recover_identity(
     json_identity: Value,
     return_tx: Option<bool>,
     token_recover: Option<bool>,
-    fee_offer: Option<f64>,
+    fee_offer: Option<Amount>,
     source_of_funds: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    let json_identity = identity_name::normalize_identity_definition(&json_identity).map_err(|e| e.to_string())?;
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
@@ -723,10 +1149,12 @@ pub async fn update_identity(
     json_identity: Value,
     return_tx: Option<bool>,
     token_update: Option<bool>,
-    fee_offer: Option<f64>,
+    fee_offer: Option<Amount>,
     source_of_funds: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    let json_identity = identity_name::normalize_identity_definition(&json_identity).map_err(|e| e.to_string())?;
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
@@ -771,10 +1199,12 @@ pub async fn set_identity_timelock(
     identity: String,
     timelock_params: Value,
     return_tx: Option<bool>,
-    fee_offer: Option<f64>,
+    fee_offer: Option<Amount>,
     source_of_funds: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    let identity = IdentityRef::parse(&identity).map_err(|e| e.to_string())?.to_rpc_param();
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
@@ -799,6 +1229,17 @@ pub async fn register_name_commitment(
     source_of_funds: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    identity_name::validate_name_component(&name)
+        .map_err(|e| format!("Invalid identity name: '{}': {}", name, e))?;
+    let identity_param = |value: Option<String>| {
+        value.filter(|v| !v.is_empty())
+            .map(|v| IdentityRef::parse(&v).map(|r| r.to_rpc_param()))
+            .transpose()
+            .map_err(|e| e.to_string())
+    };
+    let referral = identity_param(referral)?;
+    let parent_name_or_id = identity_param(parent_name_or_id)?;
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
@@ -847,10 +1288,12 @@ pub async fn register_identity(
     namereservation: Value,
     identity: Value,
     return_tx: Option<bool>,
-    fee_offer: Option<f64>,
+    fee_offer: Option<Amount>,
     source_of_funds: Option<String>,
     state: State<'_, AppState>
 ) -> Result<Value, String> {
+    let identity = identity_name::normalize_identity_definition(&identity).map_err(|e| e.to_string())?;
+
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
 
@@ -874,7 +1317,7 @@ pub async fn register_identity(
 
         // If we have source_of_funds, we MUST include feeoffer (even if 0) to maintain position
         if source_of_funds.is_some() {
-            let fee = fee_offer.unwrap_or(0.0);
+            let fee = fee_offer.unwrap_or(Amount::ZERO);
             params.push(serde_json::json!(fee));
 
             if let Some(source) = source_of_funds {
@@ -892,6 +1335,56 @@ pub async fn register_identity(
         .map_err(|e| e.to_string())
 }
 
+/// Fee and eligibility for a sub-ID under a currency parent
+#[tauri::command]
+pub async fn quote_sub_identity(
+    name: String,
+    parent: String,
+    with_referral: Option<bool>,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.quote_sub_identity(&name, &parent, with_referral.unwrap_or(false), chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Submit the name commitment for a sub-ID; finish with `complete_sub_identity` once it confirms
+#[tauri::command]
+pub async fn commit_sub_identity(
+    request: SubIdRegistrationRequest,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.commit_sub_identity(&request, chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Register a sub-ID from a confirmed commitment returned by `commit_sub_identity`
+#[tauri::command]
+pub async fn complete_sub_identity(
+    commitment: SubIdCommitment,
+    chain: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.complete_sub_identity(&commitment, chain.as_deref())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 // Wallet-specific commands
 #[tauri::command]
 pub async fn list_transactions(
@@ -917,6 +1410,35 @@ pub async fn list_transactions(
     .map_err(|e| e.to_string())
 }
 
+/// Classified wallet history, newest first, one page at a time
+#[tauri::command]
+pub async fn get_wallet_history(
+    cursor: Option<u32>,
+    limit: Option<u32>,
+    filter: Option<HistoryFilter>,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_wallet_history(cursor.unwrap_or(0), limit.unwrap_or(50), &filter.unwrap_or_default())
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Write wallet history for a date or block range to CSV and JSON files
+#[tauri::command]
+pub async fn export_transactions(request: ExportRequest, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.export_transactions(&request)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_currency_balance(
     address: String,
@@ -944,7 +1466,7 @@ pub async fn get_currency_balance(
 // Currency Definition Commands
 #[tauri::command]
 pub async fn define_currency(
-    currency_definition: CurrencyDefinition,
+    currency_definition: DefineCurrencyRequest,
     fractional_gateway: Option<bool>,
     reserves: Option<Vec<String>>,
     state: State<'_, AppState>
@@ -952,7 +1474,7 @@ pub async fn define_currency(
     // Debug logging
     eprintln!("Backend received define_currency with:");
     eprintln!("  name: {}", currency_definition.name);
-    eprintln!("  options: {}", currency_definition.options);
+    eprintln!("  options: {}", currency_definition.options.bits());
     eprintln!("  proofprotocol: {:?}", currency_definition.proofprotocol);
     eprintln!("  currencies: {:?}", currency_definition.currencies);
     eprintln!("  maxpreconversion: {:?}", currency_definition.maxpreconversion);
@@ -963,7 +1485,127 @@ pub async fn define_currency(
     let client_guard = state.active_client.read().await;
     let client = client_guard.as_ref().ok_or("No active RPC connection")?;
     
-    client.define_currency(&currency_definition, fractional_gateway, reserves)
+    let response = client.define_currency(&currency_definition, fractional_gateway, reserves)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Sub-namespace currencies are picked up from the identity once the definition is mined
+    if currency_definition.parent.is_none() {
+        state.launch_tracker.track(&currency_definition.name);
+    }
+
+    Ok(serde_json::to_value(response).unwrap())
+}
+
+/// Launch status of every currency this wallet defined
+#[tauri::command]
+pub async fn get_currency_launches(state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.get_launch_statuses(&state.launch_tracker)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn track_currency_launch(currency: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.launch_tracker.track(&currency);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn untrack_currency_launch(currency: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.launch_tracker.untrack(&currency);
+    Ok(())
+}
+
+/// Poll tracked launches in the background, emitting an event whenever one changes
+#[tauri::command]
+pub async fn start_launch_tracker(
+    interval_secs: Option<u64>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let tracker = state.launch_tracker.clone();
+    let Some(generation) = tracker.start() else {
+        return Ok(());
+    };
+
+    let active_client = state.active_client.clone();
+    let interval = Duration::from_secs(interval_secs.unwrap_or(30).max(5));
+
+    tauri::async_runtime::spawn(async move {
+        while tracker.is_current(generation) {
+            // Release the lock before polling so a reconnect is not held up
+            let client = active_client.read().await.clone();
+            let statuses = match client {
+                Some(client) => client.get_launch_statuses(&tracker).await,
+                None => Ok(Vec::new()),
+            };
+
+            match statuses {
+                Ok(statuses) => {
+                    for status in statuses.iter().filter(|s| tracker.record(s)) {
+                        if let Err(e) = app.emit(LAUNCH_EVENT, status) {
+                            eprintln!("Failed to emit launch event: {}", e);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Launch tracker poll failed: {}", e),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_launch_tracker(state: State<'_, AppState>) -> Result<(), String> {
+    state.launch_tracker.stop();
+    Ok(())
+}
+
+/// Check a currency definition against chain state without submitting it
+#[tauri::command]
+pub async fn validate_currency_definition(
+    currency_definition: DefineCurrencyRequest,
+    state: State<'_, AppState>
+) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.validate_currency_definition(&currency_definition)
+        .await
+        .map(|r| serde_json::to_value(r).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// Convert currency options between the integer and flag names, reporting invalid combinations
+#[tauri::command]
+pub async fn convert_currency_options(
+    options: Option<u32>,
+    names: Option<Vec<String>>
+) -> Result<Value, String> {
+    let options = match (options, names) {
+        (Some(bits), None) => CurrencyOptions::from_bits(bits),
+        (None, Some(names)) => CurrencyOptions::from_names(&names).map_err(|e| e.to_string())?,
+        _ => return Err("Provide either options or names".to_string()),
+    };
+
+    Ok(serde_json::to_value(CurrencyOptionsInfo::from(options)).unwrap())
+}
+
+/// Decode a transaction and label what it spends and creates, before signing or broadcasting it
+#[tauri::command]
+pub async fn inspect_raw_transaction(hex: String, state: State<'_, AppState>) -> Result<Value, String> {
+    let client_guard = state.active_client.read().await;
+    let client = client_guard.as_ref().ok_or("No active RPC connection")?;
+
+    client.inspect_raw_transaction(&hex)
         .await
         .map(|r| serde_json::to_value(r).unwrap())
         .map_err(|e| e.to_string())
@@ -11,7 +11,19 @@ use commands::{
     convert_to_verusidx, register_name_commitment, register_identity, revoke_identity, recover_identity, update_identity, set_identity_timelock,
     list_transactions, get_currency_balance, get_new_address, get_addresses_by_account,
     z_get_new_address, z_list_addresses, z_get_operation_status, define_currency, send_raw_transaction,
-    send_ticket_to_graveyard
+    send_ticket_to_graveyard,
+    get_vdxf_id, build_content_multimap, decode_content_multimap, get_identity_timeline,
+    audit_identity_authorities, quote_sub_identity, commit_sub_identity, complete_sub_identity,
+    parse_identity_name, resolve_identity, convert_currency_options,
+    validate_currency_definition, get_currency_launches, track_currency_launch, untrack_currency_launch,
+    start_launch_tracker, stop_launch_tracker, get_currency_series, get_block_header,
+    get_chain_cache_info, clear_chain_cache, find_conversion_routes,
+    convert_currency_protected, check_conversion_settlement, validate_send_outputs,
+    send_currency_outputs, get_market_offers, list_wallet_offers,
+    start_offer_monitor, stop_offer_monitor, get_order_book,
+    validate_make_offer, make_offer_checked, validate_take_offer, take_offer_checked,
+    verify_offer, get_wallet_history, inspect_raw_transaction, export_transactions,
+    estimate_send_fees, track_cross_chain_transfer
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -71,7 +83,48 @@ pub fn run() {
             z_get_operation_status,
             define_currency,
             send_raw_transaction,
-            send_ticket_to_graveyard
+            send_ticket_to_graveyard,
+            get_vdxf_id,
+            build_content_multimap,
+            decode_content_multimap,
+            get_identity_timeline,
+            audit_identity_authorities,
+            quote_sub_identity,
+            commit_sub_identity,
+            complete_sub_identity,
+            parse_identity_name,
+            resolve_identity,
+            convert_currency_options,
+            validate_currency_definition,
+            get_currency_launches,
+            track_currency_launch,
+            untrack_currency_launch,
+            start_launch_tracker,
+            stop_launch_tracker,
+            get_currency_series,
+            get_block_header,
+            get_chain_cache_info,
+            clear_chain_cache,
+            find_conversion_routes,
+            convert_currency_protected,
+            check_conversion_settlement,
+            validate_send_outputs,
+            send_currency_outputs,
+            get_market_offers,
+            list_wallet_offers,
+            start_offer_monitor,
+            stop_offer_monitor,
+            get_order_book,
+            validate_make_offer,
+            make_offer_checked,
+            validate_take_offer,
+            take_offer_checked,
+            verify_offer,
+            get_wallet_history,
+            inspect_raw_transaction,
+            export_transactions,
+            estimate_send_fees,
+            track_cross_chain_transfer
         ]);
     
     // Add the opener plugin
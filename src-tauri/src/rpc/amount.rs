@@ -0,0 +1,204 @@
+// Fixed-point Currency Amounts
+use crate::rpc::RpcError;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Decimal places of every Verus currency amount
+pub const AMOUNT_DECIMALS: usize = 8;
+
+/// Satoshis per whole unit of currency
+pub const SATOSHIS_PER_COIN: i64 = 100_000_000;
+
+/// Largest satoshi count that survives a round trip through an f64 decimal
+const MAX_EXACT_F64_SATS: u64 = 1_000_000_000_000_000;
+
+/// An exact currency amount held as satoshis
+///
+/// Parses and formats with 8 decimals, and deserializes from either a JSON number
+/// (as the daemon reports amounts) or a decimal string. Serializes as a number, so
+/// the daemon and the frontend see the same shape as before, except for amounts
+/// too large to be exact as one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(i64);
+
+impl Amount {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_sats(sats: i64) -> Self {
+        Self(sats)
+    }
+
+    pub const fn sats(self) -> i64 {
+        self.0
+    }
+
+    /// Round a floating point value to the nearest satoshi
+    pub fn from_f64(value: f64) -> Result<Self, RpcError> {
+        let sats = (value * SATOSHIS_PER_COIN as f64).round();
+        if !sats.is_finite() || sats.abs() >= i64::MAX as f64 {
+            return Err(RpcError::ValidationError(format!("Amount {} is out of range", value)));
+        }
+        Ok(Self(sats as i64))
+    }
+
+    /// Approximate value for price and ratio calculations
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SATOSHIS_PER_COIN as f64
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn checked_mul(self, factor: i64) -> Option<Self> {
+        self.0.checked_mul(factor).map(Self)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let coin = SATOSHIS_PER_COIN as u64;
+        write!(f, "{}{}.{:0width$}", sign, abs / coin, abs % coin, width = AMOUNT_DECIMALS)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = RpcError;
+
+    /// Parse a decimal such as `12`, `-0.5` or `1.23456789`; more than 8 decimals is an error
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RpcError::ValidationError(format!("Invalid amount '{}'", s));
+        let trimmed = s.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        if fraction.len() > AMOUNT_DECIMALS {
+            return Err(RpcError::ValidationError(format!(
+                "Amount '{}' has more than {} decimal places", s, AMOUNT_DECIMALS
+            )));
+        }
+
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+        let fraction: i64 = format!("{:0<width$}", fraction, width = AMOUNT_DECIMALS).parse().map_err(|_| invalid())?;
+        let amount = Self(whole).checked_mul(SATOSHIS_PER_COIN)
+            .and_then(|w| w.checked_add(Self(fraction)))
+            .ok_or_else(invalid)?;
+        Ok(if negative { Self(-amount.0) } else { amount })
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Up to 15 significant digits the shortest f64 representation is the exact
+        // decimal; larger amounts go as a string, which the daemon also accepts
+        if self.0.unsigned_abs() < MAX_EXACT_F64_SATS {
+            serializer.serialize_f64(self.to_f64())
+        } else {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl Visitor<'_> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount as a number or decimal string")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+                value.checked_mul(SATOSHIS_PER_COIN)
+                    .map(Amount)
+                    .ok_or_else(|| E::custom(format!("Amount {} is out of range", value)))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+                i64::try_from(value)
+                    .map_err(|_| E::custom(format!("Amount {} is out of range", value)))
+                    .and_then(|v| self.visit_i64(v))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Amount, E> {
+                Amount::from_f64(value).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_format() {
+        assert_eq!("1.5".parse::<Amount>().unwrap(), Amount::from_sats(150_000_000));
+        assert_eq!(".00000001".parse::<Amount>().unwrap(), Amount::from_sats(1));
+        assert_eq!("-0.1".parse::<Amount>().unwrap().to_string(), "-0.10000000");
+        assert_eq!(Amount::from_sats(2_100_000_000_000_001).to_string(), "21000000.00000001");
+        assert!("1.123456789".parse::<Amount>().is_err());
+        assert!("1e8".parse::<Amount>().is_err());
+        assert!(".".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn test_serde_accepts_numbers_and_strings() {
+        let amounts: Vec<Amount> = serde_json::from_value(json!([0.1, "0.2", 3, 0.30000001])).unwrap();
+        assert_eq!(
+            amounts.iter().map(|a| a.sats()).collect::<Vec<_>>(),
+            vec![10_000_000, 20_000_000, 300_000_000, 30_000_001]
+        );
+
+        // 0.1 + 0.2 is exact in satoshis and serializes to the literal decimal
+        let sum = amounts[0].checked_add(amounts[1]).unwrap();
+        assert_eq!(serde_json::to_string(&sum).unwrap(), "0.3");
+        assert_eq!(serde_json::to_string(&Amount::from_sats(12_345_678_901)).unwrap(), "123.45678901");
+        assert_eq!(serde_json::to_string(&Amount::from_sats(2_100_000_000_000_001)).unwrap(), "\"21000000.00000001\"");
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(Amount::from_sats(i64::MAX).checked_add(Amount::from_sats(1)), None);
+        assert_eq!(Amount::from_sats(i64::MAX).checked_mul(2), None);
+        assert_eq!(Amount::ZERO.checked_sub(Amount::from_sats(1)), Some(Amount::from_sats(-1)));
+        assert!("92233720368.54775808".parse::<Amount>().is_err());
+    }
+}
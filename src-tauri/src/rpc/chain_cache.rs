@@ -0,0 +1,409 @@
+// Per-chain On-disk Cache for Immutable Chain Data
+use crate::rpc::{VerusRpcClient, RpcError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Entries are only written once buried this deep, and dropped if a reorg brings the tip this close
+pub const CACHE_REORG_DEPTH: u64 = 10;
+
+const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+const MAX_ENTRY_BYTES: u64 = 4 * 1024 * 1024;
+const INDEX_FILE: &str = "index.json";
+
+/// How long a fetched tip height is trusted before asking the daemon again
+const TIP_REFRESH: Duration = Duration::from_secs(15);
+
+/// Result fields that depend on the wallet's keys rather than the chain, so go stale once keys are imported
+const WALLET_FIELDS: [&str; 2] = ["canspendfor", "cansignfor"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    key: String,
+    method: String,
+    height: u64,
+    bytes: u64,
+    /// Position in access order, for LRU eviction
+    last_used: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredValue {
+    key: String,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MethodUsage {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheInfo {
+    pub chain: String,
+    pub directory: String,
+    pub entries: usize,
+    pub bytes: u64,
+    pub max_bytes: u64,
+    pub reorg_depth: u64,
+    pub methods: BTreeMap<String, MethodUsage>,
+}
+
+/// File-per-entry store with a JSON index, one directory per chain
+#[derive(Debug)]
+pub struct CacheStore {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<HashMap<String, IndexEntry>>,
+    access_counter: AtomicU64,
+}
+
+/// FNV-1a, used for file names because it is stable across builds
+fn fnv1a(data: &str) -> u64 {
+    data.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Cache key for a call; the height is part of the key since the same params can be asked at several heights
+pub fn cache_key(method: &str, params: &Value, height: u64) -> String {
+    format!("{}@{}:{}", method, height, params)
+}
+
+/// A result without its wallet-relative fields, as stored on disk
+fn without_wallet_fields(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        for field in WALLET_FIELDS {
+            object.remove(field);
+        }
+    }
+    value
+}
+
+impl CacheStore {
+    pub fn open(dir: &Path, max_bytes: u64) -> Result<Self, RpcError> {
+        fs::create_dir_all(dir)
+            .map_err(|e| RpcError::Configuration(format!("Cannot create cache directory {}: {}", dir.display(), e)))?;
+
+        // A missing or corrupt index just starts the cache over
+        let index: HashMap<String, IndexEntry> = fs::read_to_string(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+
+        let access_counter = AtomicU64::new(index.values().map(|e| e.last_used).max().unwrap_or(0) + 1);
+        Ok(Self { dir: dir.to_path_buf(), max_bytes, index: Mutex::new(index), access_counter })
+    }
+
+    fn next_access(&self) -> u64 {
+        self.access_counter.fetch_add(1, Ordering::SeqCst)
+    }
+
+    fn file_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(key)))
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let file_id = format!("{:016x}", fnv1a(key));
+        let mut index = self.index.lock().ok()?;
+        let entry = index.get_mut(&file_id).filter(|e| e.key == key)?;
+
+        let stored = fs::read_to_string(self.file_for(key))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<StoredValue>(&raw).ok())
+            .filter(|s| s.key == key);
+        match stored {
+            Some(stored) => {
+                entry.last_used = self.next_access();
+                Some(stored.value)
+            }
+            None => {
+                index.remove(&file_id);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: &str, method: &str, height: u64, value: &Value) {
+        let Ok(raw) = serde_json::to_string(&StoredValue { key: key.to_string(), value: value.clone() }) else {
+            return;
+        };
+        let bytes = raw.len() as u64;
+        if bytes > MAX_ENTRY_BYTES || bytes > self.max_bytes {
+            return;
+        }
+        if fs::write(self.file_for(key), &raw).is_err() {
+            return;
+        }
+
+        let Ok(mut index) = self.index.lock() else {
+            return;
+        };
+        index.insert(format!("{:016x}", fnv1a(key)), IndexEntry {
+            key: key.to_string(),
+            method: method.to_string(),
+            height,
+            bytes,
+            last_used: self.next_access(),
+        });
+        self.evict(&mut index);
+        self.save_index(&index);
+    }
+
+    /// Drop least recently used entries until the cache fits its size limit
+    fn evict(&self, index: &mut HashMap<String, IndexEntry>) {
+        let mut total: u64 = index.values().map(|e| e.bytes).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+
+        let mut by_age: Vec<(String, u64, u64)> = index.iter()
+            .map(|(id, e)| (id.clone(), e.last_used, e.bytes))
+            .collect();
+        by_age.sort_by_key(|(_, last_used, _)| *last_used);
+
+        for (id, _, bytes) in by_age {
+            if total <= self.max_bytes {
+                break;
+            }
+            let _ = fs::remove_file(self.dir.join(format!("{}.json", id)));
+            index.remove(&id);
+            total -= bytes;
+        }
+    }
+
+    /// Remove entries above a height, e.g. after a reorg
+    pub fn invalidate_above(&self, height: u64) -> usize {
+        self.remove_where(|e| e.height > height)
+    }
+
+    /// Remove every entry, or only those for one RPC method
+    pub fn clear(&self, method: Option<&str>) -> usize {
+        self.remove_where(|e| method.is_none_or(|m| e.method == m))
+    }
+
+    fn remove_where(&self, predicate: impl Fn(&IndexEntry) -> bool) -> usize {
+        let Ok(mut index) = self.index.lock() else {
+            return 0;
+        };
+        let doomed: Vec<String> = index.iter()
+            .filter(|(_, e)| predicate(e))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &doomed {
+            let _ = fs::remove_file(self.dir.join(format!("{}.json", id)));
+            index.remove(id);
+        }
+        if !doomed.is_empty() {
+            self.save_index(&index);
+        }
+        doomed.len()
+    }
+
+    fn save_index(&self, index: &HashMap<String, IndexEntry>) {
+        if let Ok(raw) = serde_json::to_string(index) {
+            if let Err(e) = fs::write(self.dir.join(INDEX_FILE), raw) {
+                eprintln!("Failed to write chain cache index: {}", e);
+            }
+        }
+    }
+
+    pub fn info(&self, chain: &str) -> CacheInfo {
+        let index = self.index.lock().map(|i| i.clone()).unwrap_or_default();
+        let mut methods: BTreeMap<String, MethodUsage> = BTreeMap::new();
+        for entry in index.values() {
+            let usage = methods.entry(entry.method.clone()).or_default();
+            usage.entries += 1;
+            usage.bytes += entry.bytes;
+        }
+
+        CacheInfo {
+            chain: chain.to_string(),
+            directory: self.dir.display().to_string(),
+            entries: index.len(),
+            bytes: index.values().map(|e| e.bytes).sum(),
+            max_bytes: self.max_bytes,
+            reorg_depth: CACHE_REORG_DEPTH,
+            methods,
+        }
+    }
+}
+
+/// Client-side handle: the store is opened on first use, once the chain name is known
+#[derive(Debug, Default)]
+pub struct ChainCache {
+    store: OnceCell<Option<(String, CacheStore)>>,
+    tip: Mutex<Option<(u64, Instant)>>,
+}
+
+impl ChainCache {
+    /// Record the latest tip, invalidating recent entries if the chain got shorter
+    ///
+    /// Only a drop in height is noticed; a reorg onto a branch at least as long goes undetected.
+    fn observe_tip(&self, store: &CacheStore, tip: u64) {
+        let previous = self.tip.lock().ok().and_then(|mut t| t.replace((tip, Instant::now())));
+        if let Some((previous_tip, _)) = previous {
+            if tip < previous_tip {
+                store.invalidate_above(tip.saturating_sub(CACHE_REORG_DEPTH));
+            }
+        }
+    }
+
+    fn fresh_tip(&self) -> Option<u64> {
+        let tip = *self.tip.lock().ok()?;
+        tip.filter(|(_, at)| at.elapsed() < TIP_REFRESH).map(|(height, _)| height)
+    }
+}
+
+fn cache_directory(chain: &str) -> Option<PathBuf> {
+    let safe: String = chain.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    dirs::cache_dir().map(|dir| dir.join("verusidx").join("chain-cache").join(safe))
+}
+
+impl VerusRpcClient {
+    /// The disk cache for the connected chain; a failed `getinfo` is retried on the next call
+    async fn chain_store(&self) -> Option<&(String, CacheStore)> {
+        self.chain_cache.store.get_or_try_init(|| async {
+            let Some(chain) = self.get_info(None).await?.name else {
+                return Ok::<_, RpcError>(None);
+            };
+            let Some(dir) = cache_directory(&chain) else {
+                return Ok(None);
+            };
+            match CacheStore::open(&dir, DEFAULT_MAX_BYTES) {
+                Ok(store) => Ok(Some((chain, store))),
+                Err(e) => {
+                    eprintln!("Chain cache disabled: {}", e);
+                    Ok(None)
+                }
+            }
+        })
+        .await
+        .ok()?
+        .as_ref()
+    }
+
+    async fn cache_tip(&self, store: &CacheStore) -> Result<u64, RpcError> {
+        if let Some(tip) = self.chain_cache.fresh_tip() {
+            return Ok(tip);
+        }
+        let tip = self.get_block_count(None).await?;
+        self.chain_cache.observe_tip(store, tip);
+        Ok(tip)
+    }
+
+    /// Call a method whose result is fixed once `height` is buried, serving it from disk when possible
+    ///
+    /// Wallet-relative fields such as `canspendfor` are left out, whether or not the result was cached.
+    pub async fn call_cached<T>(&self, method: &str, params: Value, height: u64) -> Result<T, RpcError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let store = match self.chain_store().await {
+            Some((_, store)) if height + CACHE_REORG_DEPTH <= self.cache_tip(store).await? => Some(store),
+            _ => None,
+        };
+
+        let key = cache_key(method, &params, height);
+        let value = match store.and_then(|s| s.get(&key)) {
+            Some(value) => value,
+            None => {
+                let value = without_wallet_fields(self.call(method, params).await?);
+                if let Some(store) = store {
+                    store.insert(&key, method, height, &value);
+                }
+                value
+            }
+        };
+
+        serde_json::from_value(value)
+            .map_err(|e| RpcError::JsonParse(format!("Failed to deserialize result for {}: {}", method, e)))
+    }
+
+    pub async fn get_chain_cache_info(&self) -> Result<CacheInfo, RpcError> {
+        let (chain, store) = self.chain_store().await
+            .ok_or_else(|| RpcError::Configuration("Chain cache is not available".to_string()))?;
+        Ok(store.info(chain))
+    }
+
+    /// Clear the cache, or only one RPC method's entries, returning how many were removed
+    pub async fn clear_chain_cache(&self, method: Option<&str>) -> Result<usize, RpcError> {
+        let (_, store) = self.chain_store().await
+            .ok_or_else(|| RpcError::Configuration("Chain cache is not available".to_string()))?;
+        Ok(store.clear(method))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_store(name: &str, max_bytes: u64) -> CacheStore {
+        let dir = std::env::temp_dir().join(format!("verusidx-cache-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        CacheStore::open(&dir, max_bytes).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_and_reopen() {
+        let store = temp_store("reopen", DEFAULT_MAX_BYTES);
+        let key = cache_key("getidentity", &json!(["alice@", 100]), 100);
+        store.insert(&key, "getidentity", 100, &json!({ "identity": { "name": "alice" } }));
+        assert_eq!(store.get(&key).unwrap()["identity"]["name"], json!("alice"));
+        assert!(store.get(&cache_key("getidentity", &json!(["alice@", 101]), 101)).is_none());
+
+        let reopened = CacheStore::open(&store.dir, DEFAULT_MAX_BYTES).unwrap();
+        assert!(reopened.get(&key).is_some());
+        assert_eq!(reopened.info("VRSC").methods["getidentity"].entries, 1);
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_wallet_fields_not_stored() {
+        let value = without_wallet_fields(json!({ "identity": { "name": "alice" }, "canspendfor": true, "cansignfor": true }));
+        assert_eq!(value, json!({ "identity": { "name": "alice" } }));
+        assert_eq!(without_wallet_fields(json!("00")), json!("00"));
+    }
+
+    #[test]
+    fn test_invalidation_and_clear() {
+        let store = temp_store("invalidate", DEFAULT_MAX_BYTES);
+        for height in [100, 200, 300] {
+            store.insert(&cache_key("getblockhash", &json!([height]), height), "getblockhash", height, &json!("00"));
+        }
+        store.insert(&cache_key("getcurrency", &json!(["VRSC", 150]), 150), "getcurrency", 150, &json!({}));
+
+        assert_eq!(store.invalidate_above(200), 1);
+        assert_eq!(store.clear(Some("getcurrency")), 1);
+        assert_eq!(store.info("VRSC").entries, 2);
+        assert_eq!(store.clear(None), 2);
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_size_limit_evicts_least_recently_used() {
+        let payload = json!("x".repeat(400));
+        let store = temp_store("evict", 1000);
+        let first = cache_key("getblock", &json!([1]), 1);
+        let second = cache_key("getblock", &json!([2]), 2);
+        let third = cache_key("getblock", &json!([3]), 3);
+
+        store.insert(&first, "getblock", 1, &payload);
+        store.insert(&second, "getblock", 2, &payload);
+        store.insert(&third, "getblock", 3, &payload);
+
+        let info = store.info("VRSC");
+        assert!(info.bytes <= 1000);
+        assert_eq!(info.entries, 2);
+        assert!(store.get(&third).is_some());
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+}
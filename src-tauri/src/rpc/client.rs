@@ -1,4 +1,7 @@
 use super::{RpcError, RpcCredentials, JsonRpcRequest, VerusJsonRpcResponse};
+use super::identity_name::NameCache;
+use super::currency_series::SeriesCache;
+use super::chain_cache::ChainCache;
 use reqwest::Client;
 use serde_json::Value;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -7,6 +10,9 @@ pub struct VerusRpcClient {
     credentials: RpcCredentials,
     client: Client,
     request_counter: AtomicU64,
+    pub(crate) name_cache: NameCache,
+    pub(crate) series_cache: SeriesCache,
+    pub(crate) chain_cache: ChainCache,
 }
 
 impl VerusRpcClient {
@@ -20,6 +26,9 @@ impl VerusRpcClient {
             credentials,
             client,
             request_counter: AtomicU64::new(1),
+            name_cache: NameCache::default(),
+            series_cache: SeriesCache::default(),
+            chain_cache: ChainCache::default(),
         })
     }
     
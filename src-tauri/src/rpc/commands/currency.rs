@@ -1,47 +1,18 @@
 // Currency Management RPC Commands
-use crate::rpc::{
-    VerusRpcClient, RpcError, Currency, DefineCurrencyResponse, 
-    CurrencyDefinition
-};
+use crate::rpc::{VerusRpcClient, RpcError, DefineCurrencyResponse, DefineCurrencyRequest};
+use crate::rpc::amount::Amount;
 use serde_json::json;
 
 impl VerusRpcClient {
-    /// List all currencies
-    pub async fn list_currencies(
-        &self,
-        query: Option<&str>,
-        verbose: Option<bool>
-    ) -> Result<Vec<Currency>, RpcError> {
-        let mut params = Vec::new();
-        if let Some(q) = query {
-            params.push(json!(q));
-            if let Some(v) = verbose {
-                params.push(json!(v));
-            }
-        }
-        self.call("listcurrencies", json!(params)).await
-    }
-
-    /// Get currency details
-    pub async fn get_currency(
-        &self,
-        currency_name: &str,
-        height: Option<u64>
-    ) -> Result<Currency, RpcError> {
-        let mut params = vec![json!(currency_name)];
-        if let Some(h) = height {
-            params.push(json!(h));
-        }
-        self.call("getcurrency", json!(params)).await
-    }
-
     /// Define new currency
     pub async fn define_currency(
         &self,
-        currency_definition: &CurrencyDefinition,
+        currency_definition: &DefineCurrencyRequest,
         fractional_gateway: Option<bool>,
         reserves: Option<Vec<String>>
     ) -> Result<DefineCurrencyResponse, RpcError> {
+        self.validate_currency_definition(currency_definition).await?.into_result()?;
+
         let mut params = vec![json!(currency_definition)];
         if let Some(gateway) = fractional_gateway {
             params.push(json!(gateway));
@@ -56,7 +27,7 @@ impl VerusRpcClient {
     pub async fn estimate_conversion(
         &self,
         currency: &str,
-        amount: f64,
+        amount: Amount,
         convert_to: &str,
         via: Option<&str>
     ) -> Result<serde_json::Value, RpcError> {
@@ -107,4 +78,43 @@ impl VerusRpcClient {
         
         self.call("getcurrencystate", json!(params)).await
     }
-}
\ No newline at end of file
+
+    /// Exports from this chain to `system`, optionally limited to a block range
+    pub async fn get_exports(
+        &self,
+        system: &str,
+        height_start: Option<u64>,
+        height_end: Option<u64>
+    ) -> Result<serde_json::Value, RpcError> {
+        let mut params = vec![json!(system)];
+        if let Some(start) = height_start {
+            params.push(json!(start));
+            if let Some(end) = height_end {
+                params.push(json!(end));
+            }
+        }
+        self.call("getexports", json!(params)).await
+    }
+
+    /// Imports into this chain from `system`, optionally limited to a block range
+    pub async fn get_imports(
+        &self,
+        system: &str,
+        height_start: Option<u64>,
+        height_end: Option<u64>
+    ) -> Result<serde_json::Value, RpcError> {
+        let mut params = vec![json!(system)];
+        if let Some(start) = height_start {
+            params.push(json!(start));
+            if let Some(end) = height_end {
+                params.push(json!(end));
+            }
+        }
+        self.call("getimports", json!(params)).await
+    }
+
+    /// Notarizations of `currency` known to this chain
+    pub async fn get_notarization_data(&self, currency: &str) -> Result<serde_json::Value, RpcError> {
+        self.call("getnotarizationdata", json!([currency])).await
+    }
+}
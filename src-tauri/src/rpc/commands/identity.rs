@@ -1,8 +1,9 @@
 // Identity Management RPC Commands
 use crate::rpc::{
     VerusRpcClient, RpcError, Identity, RegisterNameCommitmentResponse,
-    IdentityRegistration, IdentityUpdate
+    IdentityRegistration, IdentityUpdate, VdxfIdResponse, IdentityHistoryResponse
 };
+use crate::rpc::amount::Amount;
 use serde_json::{json, Value};
 
 impl VerusRpcClient {
@@ -50,7 +51,13 @@ impl VerusRpcClient {
                 }
             }
         }
-        self.call_with_chain("getidentity", json!(params), chain).await
+
+        // An identity as of a fixed height never changes once that block is buried; the
+        // snapshot leaves out the wallet's canspendfor and cansignfor, which can change
+        match height {
+            Some(h) => self.call_cached("getidentity", json!(params), h).await,
+            None => self.call_with_chain("getidentity", json!(params), chain).await,
+        }
     }
 
     /// Get identity content with full contentmultimap history
@@ -82,15 +89,63 @@ impl VerusRpcClient {
         self.call_with_chain("getidentitycontent", json!(params), chain).await
     }
 
+    /// Get every revision of an identity within a height range
+    pub async fn get_identity_history(
+        &self,
+        name: &str,
+        heightstart: Option<u64>,
+        heightend: Option<u64>,
+        chain: Option<&str>
+    ) -> Result<IdentityHistoryResponse, RpcError> {
+        let mut params = vec![json!(name)];
+
+        if let Some(start) = heightstart {
+            params.push(json!(start));
+            if let Some(end) = heightend {
+                params.push(json!(end));
+            }
+        } else if let Some(end) = heightend {
+            // heightstart is positional, 0 means from the beginning
+            params.push(json!(0));
+            params.push(json!(end));
+        }
+
+        self.call_with_chain("getidentityhistory", json!(params), chain).await
+    }
+
+    /// Resolve a VDXF qualified name (e.g. `vlotto.ledger.data`) to its i-address
+    pub async fn get_vdxf_id(
+        &self,
+        vdxf_uri: &str,
+        initial_data: Option<Value>,
+        chain: Option<&str>
+    ) -> Result<VdxfIdResponse, RpcError> {
+        let mut params = vec![json!(vdxf_uri)];
+        if let Some(data) = initial_data {
+            params.push(data);
+        }
+        self.call_with_chain("getvdxfid", json!(params), chain).await
+    }
+
     /// Reserve identity name (step 1 of registration)
     pub async fn register_name_commitment(
         &self,
         name: &str,
         control_address: &str,
-        referral: Option<&str>
+        referral: Option<&str>,
+        parent: Option<&str>,
+        source_of_funds: Option<&str>
     ) -> Result<RegisterNameCommitmentResponse, RpcError> {
         let mut params = vec![json!(name), json!(control_address)];
-        if let Some(ref_addr) = referral {
+
+        // referral and parent are positional, so empty strings hold their place
+        if parent.is_some() || source_of_funds.is_some() {
+            params.push(json!(referral.unwrap_or("")));
+            params.push(json!(parent.unwrap_or("")));
+            if let Some(source) = source_of_funds {
+                params.push(json!(source));
+            }
+        } else if let Some(ref_addr) = referral {
             params.push(json!(ref_addr));
         }
         self.call("registernamecommitment", json!(params)).await
@@ -137,7 +192,7 @@ impl VerusRpcClient {
         identity: &str,
         timelock_params: &Value,
         return_tx: Option<bool>,
-        fee_offer: Option<f64>,
+        fee_offer: Option<Amount>,
         source_of_funds: Option<&str>
     ) -> Result<Value, RpcError> {
         let mut params = vec![json!(identity), timelock_params.clone()];
@@ -162,7 +217,7 @@ impl VerusRpcClient {
         name_or_id: &str,
         return_tx: Option<bool>,
         token_revoke: Option<bool>,
-        fee_offer: Option<f64>,
+        fee_offer: Option<Amount>,
         source_of_funds: Option<&str>
     ) -> Result<Value, RpcError> {
         let mut params = vec![json!(name_or_id)];
@@ -190,7 +245,7 @@ impl VerusRpcClient {
         json_identity: &Value,
         return_tx: Option<bool>,
         token_recover: Option<bool>,
-        fee_offer: Option<f64>,
+        fee_offer: Option<Amount>,
         source_of_funds: Option<&str>
     ) -> Result<Value, RpcError> {
         let mut params = vec![json_identity.clone()];
@@ -1,6 +1,6 @@
 // Marketplace RPC Commands
 use crate::rpc::{
-    VerusRpcClient, RpcError, Offer, MakeOfferResponse, TakeOfferResponse
+    VerusRpcClient, RpcError, MakeOfferResponse, TakeOfferResponse
 };
 use serde_json::{json, Value};
 
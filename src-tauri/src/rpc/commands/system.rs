@@ -13,6 +13,12 @@ impl VerusRpcClient {
         self.call_with_chain("getblockcount", json!([]), chain).await
     }
 
+    /// Get the header of the block at a height, cached once buried
+    pub async fn get_block_header(&self, height: u64) -> Result<serde_json::Value, RpcError> {
+        let hash: String = self.call_cached("getblockhash", json!([height]), height).await?;
+        self.call_cached("getblockheader", json!([hash]), height).await
+    }
+
     /// Get general system info
     pub async fn get_info(&self, chain: Option<&str>) -> Result<GetInfoResponse, RpcError> {
         self.call_with_chain("getinfo", json!([]), chain).await
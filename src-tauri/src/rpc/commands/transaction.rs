@@ -3,6 +3,9 @@ use crate::rpc::{
     VerusRpcClient, RpcError,
     SendCurrencyRequest
 };
+use crate::rpc::amount::Amount;
+use crate::rpc::fee_estimator::DEFAULT_TRANSACTION_FEE;
+use crate::rpc::send_builder::check_send_outputs;
 use serde_json::json;
 
 impl VerusRpcClient {
@@ -12,9 +15,11 @@ impl VerusRpcClient {
         from_address: &str,
         outputs: Vec<SendCurrencyRequest>,
         min_conf: Option<u32>,
-        fee_amount: Option<f64>,
+        fee_amount: Option<Amount>,
         subtract_fee_from_amount: Option<bool>
     ) -> Result<String, RpcError> {
+        check_send_outputs(&outputs)?;
+
         let mut params = vec![json!(from_address), json!(outputs)];
         
         if let Some(conf) = min_conf {
@@ -36,29 +41,36 @@ impl VerusRpcClient {
             .map(|s| s.to_string())
     }
 
+    /// Build a `sendcurrency` transaction without signing or broadcasting it
+    pub async fn send_currency_template(
+        &self,
+        from_address: &str,
+        outputs: Vec<SendCurrencyRequest>,
+        min_conf: Option<u32>,
+        fee_amount: Option<Amount>
+    ) -> Result<serde_json::Value, RpcError> {
+        check_send_outputs(&outputs)?;
+
+        // returntx is positional, so minconf and feeamount must be filled in before it
+        let params = json!([
+            from_address,
+            outputs,
+            min_conf.unwrap_or(1),
+            fee_amount.unwrap_or(DEFAULT_TRANSACTION_FEE),
+            true
+        ]);
+        self.call("sendcurrency", params).await
+    }
+
     /// Simple currency send
     pub async fn send_currency_simple(
         &self,
         from_address: &str,
         currency: &str,
-        amount: f64,
+        amount: Amount,
         to_address: &str
     ) -> Result<String, RpcError> {
-        let output = SendCurrencyRequest {
-            currency: Some(currency.to_string()),
-            amount,
-            address: to_address.to_string(),
-            convertto: None,
-            via: None,
-            exportto: None,
-            exportcurrency: None,
-            exportid: None,
-            preconvert: None,
-            memo: None,
-            feecurrency: None,
-            addconversionfees: None,
-        };
-
+        let output = SendCurrencyRequest::new(to_address, currency, amount);
         self.send_currency(from_address, vec![output], None, None, None).await
     }
 
@@ -67,25 +79,12 @@ impl VerusRpcClient {
         &self,
         from_address: &str,
         source_currency: &str,
-        amount: f64,
+        amount: Amount,
         target_currency: &str,
         to_address: &str
     ) -> Result<String, RpcError> {
-        let output = SendCurrencyRequest {
-            currency: Some(source_currency.to_string()),
-            amount,
-            address: to_address.to_string(),
-            convertto: Some(target_currency.to_string()),
-            via: None,
-            exportto: None,
-            exportcurrency: None,
-            exportid: None,
-            preconvert: None,
-            memo: None,
-            feecurrency: None,
-            addconversionfees: None,
-        };
-
+        let output = SendCurrencyRequest::new(to_address, source_currency, amount)
+            .convert_to(target_currency);
         self.send_currency(from_address, vec![output], None, None, None).await
     }
 
@@ -94,25 +93,12 @@ impl VerusRpcClient {
         &self,
         from_address: &str,
         currency: &str,
-        amount: f64,
+        amount: Amount,
         destination_chain: &str,
         to_address: &str
     ) -> Result<String, RpcError> {
-        let output = SendCurrencyRequest {
-            currency: Some(currency.to_string()),
-            amount,
-            address: to_address.to_string(),
-            convertto: None,
-            via: None,
-            exportto: Some(destination_chain.to_string()),
-            exportcurrency: None,
-            exportid: None,
-            preconvert: None,
-            memo: None,
-            feecurrency: None,
-            addconversionfees: None,
-        };
-
+        let output = SendCurrencyRequest::new(to_address, currency, amount)
+            .export_to(destination_chain);
         self.send_currency(from_address, vec![output], None, None, None).await
     }
 
@@ -124,21 +110,9 @@ impl VerusRpcClient {
         destination_chain: &str,
         to_address: &str
     ) -> Result<String, RpcError> {
-        let output = SendCurrencyRequest {
-            currency: Some(currency.to_string()),
-            amount: 0.0,
-            address: to_address.to_string(),
-            convertto: None,
-            via: None,
-            exportto: Some(destination_chain.to_string()),
-            exportcurrency: Some(true),
-            exportid: None,
-            preconvert: None,
-            memo: None,
-            feecurrency: None,
-            addconversionfees: None,
-        };
-
+        let output = SendCurrencyRequest::new(to_address, currency, Amount::ZERO)
+            .export_to(destination_chain)
+            .export_currency();
         self.send_currency(from_address, vec![output], None, None, None).await
     }
 
@@ -149,24 +123,40 @@ impl VerusRpcClient {
         destination_chain: &str,
         to_address: &str
     ) -> Result<String, RpcError> {
-        let output = SendCurrencyRequest {
-            currency: None, // Not needed for identity export
-            amount: 0.0,
-            address: to_address.to_string(),
-            convertto: None,
-            via: None,
-            exportto: Some(destination_chain.to_string()),
-            exportcurrency: None,
-            exportid: Some(true),
-            preconvert: None,
-            memo: None,
-            feecurrency: None,
-            addconversionfees: None,
-        };
-
+        let output = SendCurrencyRequest::identity_export(to_address, destination_chain);
         self.send_currency(from_address, vec![output], None, None, None).await
     }
 
+    /// Get details of a wallet transaction
+    pub async fn get_transaction(
+        &self,
+        txid: &str,
+        include_watchonly: Option<bool>,
+        chain: Option<&str>
+    ) -> Result<serde_json::Value, RpcError> {
+        let mut params = vec![json!(txid)];
+        if let Some(watchonly) = include_watchonly {
+            params.push(json!(watchonly));
+        }
+        self.call_with_chain("gettransaction", json!(params), chain).await
+    }
+
+    /// Decode a serialized transaction without broadcasting it
+    pub async fn decode_raw_transaction(&self, hex: &str) -> Result<serde_json::Value, RpcError> {
+        self.call("decoderawtransaction", json!([hex])).await
+    }
+
+    /// A transaction by txid, decoded when `verbose`; needs -txindex for ones the wallet did not create
+    pub async fn get_raw_transaction(&self, txid: &str, verbose: bool) -> Result<serde_json::Value, RpcError> {
+        // The daemon reads verbose as a number
+        self.call("getrawtransaction", json!([txid, verbose as u8])).await
+    }
+
+    /// Unspent output, including ones created in the mempool; `None` once spent
+    pub async fn get_tx_out(&self, txid: &str, vout: u32) -> Result<Option<serde_json::Value>, RpcError> {
+        self.call("gettxout", json!([txid, vout, true])).await
+    }
+
     /// Broadcast raw transaction
     pub async fn send_raw_transaction(
         &self,
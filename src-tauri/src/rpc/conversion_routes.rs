@@ -0,0 +1,245 @@
+// Basket Conversion Route Finder
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::amount::Amount;
+use crate::rpc::currency_definition::{CurrencyDefinition, CurrencyState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One way of converting the source currency into the destination
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversionRoute {
+    /// Basket to convert through, `None` when one side is the basket itself
+    pub via: Option<String>,
+    pub estimated_output: Amount,
+    /// Conversion fees charged on the input, in the source currency
+    pub fees: Amount,
+    /// Output rate at the basket's current prices, in destination per source unit
+    pub spot_rate: Option<f64>,
+    /// Fraction of value lost to moving the basket's price, 0.01 = 1%
+    pub price_impact: Option<f64>,
+}
+
+/// A candidate route the daemon could not estimate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnavailableRoute {
+    pub via: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionRoutes {
+    pub source: String,
+    pub destination: String,
+    pub amount: Amount,
+    /// Best route first
+    pub routes: Vec<ConversionRoute>,
+    pub unavailable: Vec<UnavailableRoute>,
+}
+
+/// The parts of an `estimateconversion` result used for ranking
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConversionEstimate {
+    pub output: Amount,
+    pub net_input: Option<Amount>,
+}
+
+/// Read an `estimateconversion` result, which may be wrapped in a `conversions` array
+pub fn parse_estimate(value: &Value) -> Option<ConversionEstimate> {
+    let estimate = match value.get("conversions").or(Some(value)) {
+        Some(Value::Array(items)) => items.first()?,
+        other => other?,
+    };
+    Some(ConversionEstimate {
+        output: Amount::deserialize(estimate.get("estimatedcurrencyout")?).ok()?,
+        net_input: estimate.get("netinputamount").and_then(|v| Amount::deserialize(v).ok()),
+    })
+}
+
+/// Price of a basket in one of its reserves
+fn price_in_reserve(state: &CurrencyState, reserve_id: &str) -> Option<f64> {
+    state.reservecurrencies.iter()
+        .find(|r| r.currencyid == reserve_id)
+        .map(|r| r.priceinreserve)
+        .filter(|p| *p > 0.0)
+}
+
+/// Destination units per source unit at the basket's current prices
+///
+/// `basket_id` is the basket whose state is given; either side may be the basket itself.
+pub fn spot_rate(state: &CurrencyState, basket_id: &str, source_id: &str, destination_id: &str) -> Option<f64> {
+    if source_id == basket_id {
+        price_in_reserve(state, destination_id)
+    } else if destination_id == basket_id {
+        price_in_reserve(state, source_id).map(|p| 1.0 / p)
+    } else {
+        Some(price_in_reserve(state, destination_id)? / price_in_reserve(state, source_id)?)
+    }
+}
+
+/// Turn an estimate into a route, measuring impact against the fee-free input
+pub fn build_route(via: Option<String>, amount: Amount, estimate: ConversionEstimate, spot_rate: Option<f64>) -> ConversionRoute {
+    let net_input = estimate.net_input.unwrap_or(amount);
+    let price_impact = spot_rate
+        .filter(|_| net_input.is_positive())
+        .map(|spot| 1.0 - (estimate.output.to_f64() / net_input.to_f64()) / spot);
+
+    ConversionRoute {
+        via,
+        estimated_output: estimate.output,
+        fees: amount.checked_sub(net_input).filter(|f| f.is_positive()).unwrap_or(Amount::ZERO),
+        spot_rate,
+        price_impact,
+    }
+}
+
+/// Highest output first; direct routes win ties since they skip a hop
+pub fn rank_routes(routes: &mut [ConversionRoute]) {
+    routes.sort_by(|a, b| {
+        b.estimated_output.cmp(&a.estimated_output)
+            .then_with(|| a.via.is_some().cmp(&b.via.is_some()))
+    });
+}
+
+/// Split a `getcurrencyconverters` entry into the basket and its latest state
+///
+/// Each entry is keyed by the basket's name, next to a `lastnotarization` holding its state.
+fn parse_converter(entry: &Value) -> Option<(CurrencyDefinition, Option<CurrencyState>)> {
+    let object = entry.as_object()?;
+    let definition = object.values()
+        .find(|v| v.get("currencyid").is_some())
+        .and_then(|v| serde_json::from_value::<CurrencyDefinition>(v.clone()).ok())?;
+    let state = object.get("lastnotarization")
+        .and_then(|n| n.get("currencystate"))
+        .and_then(|s| serde_json::from_value(s.clone()).ok());
+    Some((definition, state))
+}
+
+fn basket_name(basket: &CurrencyDefinition) -> String {
+    basket.fullyqualifiedname.clone().unwrap_or_else(|| basket.name.clone())
+}
+
+impl VerusRpcClient {
+    /// Estimate every direct and one-basket route from `source` to `destination`
+    pub async fn find_conversion_routes(
+        &self,
+        source: &str,
+        destination: &str,
+        amount: Amount
+    ) -> Result<ConversionRoutes, RpcError> {
+        if !amount.is_positive() {
+            return Err(RpcError::ValidationError("Amount must be positive".to_string()));
+        }
+        let source_def = self.get_currency(source, None).await?;
+        let destination_def = self.get_currency(destination, None).await?;
+        if source_def.currencyid == destination_def.currencyid {
+            return Err(RpcError::ValidationError("Source and destination are the same currency".to_string()));
+        }
+
+        // (via, basket id, basket state)
+        let mut candidates: Vec<(Option<String>, String, Option<CurrencyState>)> = Vec::new();
+        if source_def.currencies.contains(&destination_def.currencyid) {
+            candidates.push((None, source_def.currencyid.clone(), source_def.bestcurrencystate.clone()));
+        } else if destination_def.currencies.contains(&source_def.currencyid) {
+            candidates.push((None, destination_def.currencyid.clone(), destination_def.bestcurrencystate.clone()));
+        }
+
+        let converters = self.get_currency_converters(vec![source, destination]).await?;
+        for (basket, state) in converters.as_array().into_iter().flatten().filter_map(parse_converter) {
+            if basket.currencyid == source_def.currencyid || basket.currencyid == destination_def.currencyid {
+                continue;
+            }
+            let state = match state.or_else(|| basket.bestcurrencystate.clone()) {
+                Some(state) => Some(state),
+                None => self.get_currency(&basket.currencyid, None).await
+                    .ok()
+                    .and_then(|b| b.bestcurrencystate),
+            };
+            candidates.push((Some(basket_name(&basket)), basket.currencyid, state));
+        }
+
+        let mut routes = Vec::new();
+        let mut unavailable = Vec::new();
+        for (via, basket_id, state) in candidates {
+            let estimate = self.estimate_conversion(source, amount, destination, via.as_deref()).await
+                .and_then(|value| parse_estimate(&value).ok_or(RpcError::InvalidResponse));
+            match estimate {
+                Ok(estimate) => {
+                    let spot = state.as_ref().and_then(|s| {
+                        spot_rate(s, &basket_id, &source_def.currencyid, &destination_def.currencyid)
+                    });
+                    routes.push(build_route(via, amount, estimate, spot));
+                }
+                Err(e) => unavailable.push(UnavailableRoute { via, error: e.to_string() }),
+            }
+        }
+        rank_routes(&mut routes);
+
+        Ok(ConversionRoutes {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            amount,
+            routes,
+            unavailable,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::currency_definition::ReserveCurrencyState;
+    use crate::rpc::test_util::{amount, VRSC, DAI, BRIDGE};
+    use serde_json::json;
+
+    fn bridge_state() -> CurrencyState {
+        let reserve = |currencyid: &str, priceinreserve: f64| ReserveCurrencyState {
+            currencyid: currencyid.to_string(),
+            weight: 0.5,
+            reserves: Amount::ZERO,
+            priceinreserve,
+        };
+        CurrencyState {
+            currencyid: BRIDGE.to_string(),
+            reservecurrencies: vec![reserve(VRSC, 2.0), reserve(DAI, 6.0)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_estimate() {
+        let wrapped = json!({ "conversions": [{ "estimatedcurrencyout": 29.5, "netinputamount": 9.975 }] });
+        assert_eq!(
+            parse_estimate(&wrapped),
+            Some(ConversionEstimate { output: amount("29.5"), net_input: Some(amount("9.975")) })
+        );
+        let bare = json!({ "estimatedcurrencyout": 4.9 });
+        assert_eq!(parse_estimate(&bare), Some(ConversionEstimate { output: amount("4.9"), net_input: None }));
+        assert_eq!(parse_estimate(&json!({ "conversions": [] })), None);
+    }
+
+    #[test]
+    fn test_spot_rate() {
+        let state = bridge_state();
+        assert_eq!(spot_rate(&state, BRIDGE, VRSC, DAI), Some(3.0));
+        assert_eq!(spot_rate(&state, BRIDGE, VRSC, BRIDGE), Some(0.5));
+        assert_eq!(spot_rate(&state, BRIDGE, BRIDGE, DAI), Some(6.0));
+        assert_eq!(spot_rate(&state, BRIDGE, VRSC, "iUnknown"), None);
+    }
+
+    #[test]
+    fn test_routes_are_ranked() {
+        let estimate = |output| ConversionEstimate { output: amount(output), net_input: Some(amount("9.975")) };
+        let mut routes = vec![
+            build_route(Some("Bridge.vETH".to_string()), amount("10"), estimate("29"), Some(3.0)),
+            build_route(Some("Pure".to_string()), amount("10"), estimate("29.5"), Some(3.0)),
+            build_route(None, amount("10"), estimate("29"), Some(3.0)),
+        ];
+        rank_routes(&mut routes);
+
+        let order: Vec<_> = routes.iter().map(|r| r.via.as_deref()).collect();
+        assert_eq!(order, vec![Some("Pure"), None, Some("Bridge.vETH")]);
+        assert_eq!(routes[0].fees, amount("0.025"));
+        let impact = routes[0].price_impact.unwrap();
+        assert!((impact - (1.0 - 29.5 / 9.975 / 3.0)).abs() < 1e-9);
+    }
+}
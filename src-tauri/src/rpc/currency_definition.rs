@@ -0,0 +1,400 @@
+// Typed Currency Definition Read Model (getcurrency / listcurrencies)
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::currency_options::CurrencyOptions;
+use crate::rpc::amount::Amount;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// How cross-chain transfers out of a system are proven
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum ProofProtocol {
+    PbaasMmr,
+    ChainId,
+    EthNotarization,
+    Unknown(u32),
+}
+
+impl From<u32> for ProofProtocol {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => ProofProtocol::PbaasMmr,
+            2 => ProofProtocol::ChainId,
+            3 => ProofProtocol::EthNotarization,
+            other => ProofProtocol::Unknown(other),
+        }
+    }
+}
+
+impl From<ProofProtocol> for u32 {
+    fn from(value: ProofProtocol) -> Self {
+        match value {
+            ProofProtocol::PbaasMmr => 1,
+            ProofProtocol::ChainId => 2,
+            ProofProtocol::EthNotarization => 3,
+            ProofProtocol::Unknown(other) => other,
+        }
+    }
+}
+
+/// How a system's notarizations are confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum NotarizationProtocol {
+    Auto,
+    NotaryConfirm,
+    NotaryChainId,
+    Unknown(u32),
+}
+
+impl From<u32> for NotarizationProtocol {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => NotarizationProtocol::Auto,
+            2 => NotarizationProtocol::NotaryConfirm,
+            3 => NotarizationProtocol::NotaryChainId,
+            other => NotarizationProtocol::Unknown(other),
+        }
+    }
+}
+
+impl From<NotarizationProtocol> for u32 {
+    fn from(value: NotarizationProtocol) -> Self {
+        match value {
+            NotarizationProtocol::Auto => 1,
+            NotarizationProtocol::NotaryConfirm => 2,
+            NotarizationProtocol::NotaryChainId => 3,
+            NotarizationProtocol::Unknown(other) => other,
+        }
+    }
+}
+
+/// Broad classification derived from the option bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrencyKind {
+    Pbaas,
+    Gateway,
+    Basket,
+    Nft,
+    Token,
+    Native,
+}
+
+impl CurrencyKind {
+    pub fn from_options(options: CurrencyOptions) -> Self {
+        if options.contains(CurrencyOptions::PBAAS) {
+            CurrencyKind::Pbaas
+        } else if options.contains(CurrencyOptions::GATEWAY) {
+            CurrencyKind::Gateway
+        } else if options.contains(CurrencyOptions::FRACTIONAL) {
+            CurrencyKind::Basket
+        } else if options.contains(CurrencyOptions::NFT_TOKEN) {
+            CurrencyKind::Nft
+        } else if options.contains(CurrencyOptions::TOKEN) {
+            CurrencyKind::Token
+        } else {
+            CurrencyKind::Native
+        }
+    }
+}
+
+/// One reserve of a fractional currency
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReserveCurrencyState {
+    pub currencyid: String,
+    pub weight: f64,
+    pub reserves: Amount,
+    pub priceinreserve: f64,
+}
+
+/// Per-currency conversion totals for the last block of a currency state
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurrencyConversionState {
+    pub reservein: Amount,
+    pub primarycurrencyin: Amount,
+    pub reserveout: Amount,
+    pub lastconversionprice: f64,
+    pub viaconversionprice: f64,
+    pub fees: Amount,
+    pub conversionfees: Amount,
+    pub priorweights: f64,
+}
+
+/// `bestcurrencystate` / `lastconfirmedcurrencystate`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurrencyState {
+    pub flags: u32,
+    pub version: u32,
+    pub currencyid: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reservecurrencies: Vec<ReserveCurrencyState>,
+    pub initialsupply: Amount,
+    pub emitted: Amount,
+    pub supply: Amount,
+    pub currencies: HashMap<String, CurrencyConversionState>,
+    pub primarycurrencyfees: Amount,
+    pub primarycurrencyconversionfees: Amount,
+    pub primarycurrencyout: Amount,
+    pub preconvertedout: Amount,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A currency as reported by `getcurrency`
+///
+/// Only `name` and `currencyid` are required; tokens, baskets, PBaaS chains and
+/// gateways each omit different fields, and anything not modelled here is kept in `extra`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrencyDefinition {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub options: CurrencyOptions,
+    /// Decoded `options`, filled in by the client
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub optionnames: Vec<String>,
+    /// Classification of `options`, filled in by the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<CurrencyKind>,
+    pub name: String,
+    pub currencyid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub systemid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notarizationprotocol: Option<NotarizationProtocol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proofprotocol: Option<ProofProtocol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nativecurrencyid: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launchsystemid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startblock: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endblock: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub currencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conversions: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub minpreconversion: Vec<Amount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maxpreconversion: Vec<Amount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub initialcontributions: Vec<Amount>,
+    /// `{"recipient i-address": amount}` entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preallocations: Vec<HashMap<String, Amount>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initialsupply: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prelaunchdiscount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prelaunchcarveout: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idregistrationfees: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idreferrallevels: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idimportfees: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currencyregistrationfee: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pbaassystemregistrationfee: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currencyimportfee: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactionimportfee: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactionexportfee: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gatewayconvertername: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gatewayconverterissuance: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notaries: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minnotariesconfirm: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billingperiod: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notarizationreward: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub eras: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currencyidhex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fullyqualifiedname: Option<String>,
+    /// i-address -> name for every currency referenced by this definition
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub currencynames: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definitiontxid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definitiontxout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bestheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastconfirmedheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bestcurrencystate: Option<CurrencyState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastconfirmedcurrencystate: Option<CurrencyState>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CurrencyDefinition {
+    /// Fill in the decoded option fields the daemon does not report
+    pub fn annotate(&mut self) {
+        self.optionnames = self.options.names();
+        self.kind = Some(CurrencyKind::from_options(self.options));
+    }
+}
+
+/// One entry of `listcurrencies`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListedCurrency {
+    pub currencydefinition: CurrencyDefinition,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bestheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub besttxid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub besttxout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bestcurrencystate: Option<CurrencyState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastconfirmedheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastconfirmedtxid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastconfirmedtxout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastconfirmedcurrencystate: Option<CurrencyState>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl VerusRpcClient {
+    /// List currencies with a query string or object, e.g. `{"systemtype": "pbaas"}`
+    pub async fn list_currencies(
+        &self,
+        query: Option<Value>,
+        verbose: Option<bool>
+    ) -> Result<Vec<ListedCurrency>, RpcError> {
+        let mut params = Vec::new();
+        if let Some(q) = query {
+            params.push(q);
+            if let Some(v) = verbose {
+                params.push(json!(v));
+            }
+        }
+
+        let mut currencies: Vec<ListedCurrency> = self.call("listcurrencies", json!(params)).await?;
+        for listed in &mut currencies {
+            listed.currencydefinition.annotate();
+        }
+        Ok(currencies)
+    }
+
+    /// Get a currency definition, optionally as of a block height
+    pub async fn get_currency(
+        &self,
+        currency_name: &str,
+        height: Option<u64>
+    ) -> Result<CurrencyDefinition, RpcError> {
+        let mut currency: CurrencyDefinition = match height {
+            Some(h) => self.call_cached("getcurrency", json!([currency_name, h]), h).await?,
+            None => self.call("getcurrency", json!([currency_name])).await?,
+        };
+        currency.annotate();
+        Ok(currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> CurrencyDefinition {
+        let path = format!("{}/fixtures/currency/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+        let raw = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        let mut currency: CurrencyDefinition = serde_json::from_str(&raw).unwrap();
+        currency.annotate();
+        currency
+    }
+
+    #[test]
+    fn test_token_fixture() {
+        let token = fixture("token");
+        assert_eq!(token.kind, Some(CurrencyKind::Token));
+        assert_eq!(token.optionnames, vec!["idreferrals", "token"]);
+        assert_eq!(token.proofprotocol, Some(ProofProtocol::ChainId));
+        assert!(token.currencies.is_empty());
+        assert_eq!(token.bestcurrencystate.unwrap().supply, "1000000".parse::<Amount>().unwrap());
+    }
+
+    #[test]
+    fn test_basket_fixture() {
+        let basket = fixture("basket");
+        assert_eq!(basket.kind, Some(CurrencyKind::Basket));
+        assert_eq!(basket.currencies.len(), 4);
+        assert_eq!(basket.currencynames.get("i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV").map(|s| s.as_str()), Some("VRSC"));
+
+        let state = basket.bestcurrencystate.unwrap();
+        assert_eq!(state.reservecurrencies.len(), 4);
+        assert!(state.currencies.contains_key("i9nwxtKuVYX4MSbeULLiK2ttVi6rUEhh4X"));
+    }
+
+    #[test]
+    fn test_pbaas_fixture() {
+        let chain = fixture("pbaas");
+        assert_eq!(chain.kind, Some(CurrencyKind::Pbaas));
+        assert_eq!(chain.notarizationprotocol, Some(NotarizationProtocol::Auto));
+        assert_eq!(chain.proofprotocol, Some(ProofProtocol::PbaasMmr));
+        assert!(!chain.eras.is_empty());
+        assert!(!chain.notaries.is_empty());
+        assert_eq!(
+            chain.preallocations[0].get("iCsaHtXmK3HJ3F4gMkCsRqF7Cz9ff6tYYT"),
+            Some(&"21000000".parse::<Amount>().unwrap())
+        );
+        assert_eq!(chain.idimportfees, Some("0.02".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_gateway_fixture_round_trips() {
+        let gateway = fixture("gateway");
+        assert_eq!(gateway.kind, Some(CurrencyKind::Gateway));
+        assert_eq!(gateway.proofprotocol, Some(ProofProtocol::EthNotarization));
+        assert_eq!(gateway.gatewayconvertername.as_deref(), Some("Bridge"));
+
+        let mut value = serde_json::to_value(&gateway).unwrap();
+        assert_eq!(value["proofprotocol"], json!(3));
+        assert_eq!(value["nativecurrencyid"]["type"], json!(0));
+
+        // Fields the model does not know about survive a round trip
+        value["newdaemonfield"] = json!({ "nested": true });
+        let reparsed: CurrencyDefinition = serde_json::from_value(value).unwrap();
+        assert_eq!(serde_json::to_value(&reparsed).unwrap()["newdaemonfield"]["nested"], json!(true));
+    }
+
+    #[test]
+    fn test_unknown_protocol_is_preserved() {
+        assert_eq!(ProofProtocol::from(9), ProofProtocol::Unknown(9));
+        assert_eq!(u32::from(NotarizationProtocol::Unknown(7)), 7);
+    }
+}
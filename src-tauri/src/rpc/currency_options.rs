@@ -0,0 +1,175 @@
+// Currency Options Bitflags
+use crate::rpc::RpcError;
+use serde::{Deserialize, Serialize};
+
+/// The `options` field of a currency definition (matches CCurrencyDefinition in the daemon)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CurrencyOptions(u32);
+
+impl CurrencyOptions {
+    pub const FRACTIONAL: Self = Self(0x01);
+    /// Only the currency's controlling identity can register sub-IDs
+    pub const ID_RESTRICTED: Self = Self(0x02);
+    pub const ID_STAKING: Self = Self(0x04);
+    pub const ID_REFERRALS: Self = Self(0x08);
+    pub const ID_REFERRAL_REQUIRED: Self = Self(0x10);
+    pub const TOKEN: Self = Self(0x20);
+    pub const SINGLE_CURRENCY: Self = Self(0x40);
+    pub const GATEWAY: Self = Self(0x80);
+    pub const PBAAS: Self = Self(0x100);
+    pub const GATEWAY_CONVERTER: Self = Self(0x200);
+    pub const GATEWAY_NAME_CONTROLLER: Self = Self(0x400);
+    pub const NFT_TOKEN: Self = Self(0x800);
+
+    const NAMES: [(Self, &'static str); 12] = [
+        (Self::FRACTIONAL, "fractional"),
+        (Self::ID_RESTRICTED, "idrestricted"),
+        (Self::ID_STAKING, "idstaking"),
+        (Self::ID_REFERRALS, "idreferrals"),
+        (Self::ID_REFERRAL_REQUIRED, "idreferralrequired"),
+        (Self::TOKEN, "token"),
+        (Self::SINGLE_CURRENCY, "singlecurrency"),
+        (Self::GATEWAY, "gateway"),
+        (Self::PBAAS, "pbaas"),
+        (Self::GATEWAY_CONVERTER, "gatewayconverter"),
+        (Self::GATEWAY_NAME_CONTROLLER, "gatewaynamecontroller"),
+        (Self::NFT_TOKEN, "nfttoken"),
+    ];
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Bits not assigned to any known option
+    pub fn unknown_bits(self) -> u32 {
+        let known = Self::NAMES.iter().fold(0, |acc, (flag, _)| acc | flag.0);
+        self.0 & !known
+    }
+
+    /// Names of the known options that are set
+    pub fn names(self) -> Vec<String> {
+        Self::NAMES.iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+
+    /// Build from option names, case-insensitive
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, RpcError> {
+        names.iter().try_fold(Self::default(), |acc, name| {
+            let name = name.as_ref().trim().to_lowercase();
+            Self::NAMES.iter()
+                .find(|(_, known)| *known == name)
+                .map(|(flag, _)| acc.union(*flag))
+                .ok_or_else(|| RpcError::ValidationError(format!("Unknown currency option '{}'", name)))
+        })
+    }
+
+    /// Combinations the daemon will refuse in a currency definition
+    pub fn errors(self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let unknown = self.unknown_bits();
+        if unknown != 0 {
+            errors.push(format!("Unknown option bits 0x{:x}", unknown));
+        }
+
+        let requires = [
+            (Self::ID_REFERRAL_REQUIRED, Self::ID_REFERRALS, "idreferralrequired requires idreferrals"),
+            (Self::GATEWAY_CONVERTER, Self::FRACTIONAL, "gatewayconverter must be a fractional basket"),
+            (Self::GATEWAY_NAME_CONTROLLER, Self::GATEWAY, "gatewaynamecontroller requires gateway"),
+            (Self::NFT_TOKEN, Self::TOKEN, "nfttoken requires token"),
+        ];
+        for (flag, required, message) in requires {
+            if self.contains(flag) && !self.contains(required) {
+                errors.push(message.to_string());
+            }
+        }
+
+        let exclusive = [
+            (Self::PBAAS, Self::GATEWAY, "A currency cannot be both a PBaaS chain and a gateway"),
+            (Self::TOKEN, Self::PBAAS, "A PBaaS chain cannot be a token"),
+            (Self::TOKEN, Self::GATEWAY, "A gateway cannot be a token"),
+            (Self::NFT_TOKEN, Self::FRACTIONAL, "An NFT token cannot be fractional"),
+        ];
+        for (a, b, message) in exclusive {
+            if self.contains(a) && self.contains(b) {
+                errors.push(message.to_string());
+            }
+        }
+
+        if self.contains(Self::FRACTIONAL) && !self.contains(Self::TOKEN) && !self.contains(Self::PBAAS) {
+            errors.push("A fractional currency must be a token or a PBaaS chain".to_string());
+        }
+        if self.contains(Self::SINGLE_CURRENCY) && !self.contains(Self::PBAAS) && !self.contains(Self::GATEWAY) {
+            errors.push("singlecurrency only applies to PBaaS chains and gateways".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Both representations of a set of options, as returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyOptionsInfo {
+    pub options: CurrencyOptions,
+    pub names: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl From<CurrencyOptions> for CurrencyOptionsInfo {
+    fn from(options: CurrencyOptions) -> Self {
+        Self {
+            options,
+            names: options.names(),
+            errors: options.errors(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        let basket = CurrencyOptions::from_names(&["Fractional", "token", "idreferrals"]).unwrap();
+        assert_eq!(basket.bits(), 41);
+        assert_eq!(basket.names(), vec!["fractional", "idreferrals", "token"]);
+        assert!(CurrencyOptions::from_names(&["bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_valid_presets() {
+        // Token, basket, restricted token with required referrals, Bridge.vETH, PBaaS chain, gateway
+        for bits in [32, 33, 34 | 8 | 16, 545, 264, 136] {
+            assert!(CurrencyOptions::from_bits(bits).errors().is_empty(), "{} should be valid", bits);
+        }
+    }
+
+    #[test]
+    fn test_invalid_combinations() {
+        let cases = [
+            CurrencyOptions::ID_REFERRAL_REQUIRED.union(CurrencyOptions::TOKEN),
+            CurrencyOptions::FRACTIONAL,
+            CurrencyOptions::PBAAS.union(CurrencyOptions::GATEWAY),
+            CurrencyOptions::NFT_TOKEN.union(CurrencyOptions::TOKEN).union(CurrencyOptions::FRACTIONAL),
+            CurrencyOptions::from_bits(0x1000 | 0x20),
+        ];
+        for options in cases {
+            assert!(!options.errors().is_empty(), "{:?} should be rejected", options);
+        }
+    }
+}
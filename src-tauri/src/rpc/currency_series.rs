@@ -0,0 +1,378 @@
+// Historical Currency State Time-Series for Charts
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::currency_definition::CurrencyState;
+use crate::rpc::amount::Amount;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Most intervals requested from `getcurrencystate` in one call
+const MAX_INTERVALS_PER_CALL: u64 = 500;
+
+/// Points at least this far below the tip are treated as final and cached
+const SERIES_FINALITY_DEPTH: u64 = 10;
+
+/// One pair's trading over an interval, as reported in `conversiondata`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumePair {
+    pub currency: String,
+    pub convertto: String,
+    pub volume: f64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversionData {
+    pub volumecurrency: String,
+    pub volumethisinterval: f64,
+    pub volumepairs: Vec<VolumePair>,
+}
+
+/// One entry of a ranged `getcurrencystate` call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrencyStatePoint {
+    pub height: u64,
+    #[serde(default)]
+    pub blocktime: u64,
+    pub currencystate: CurrencyState,
+    #[serde(default)]
+    pub conversiondata: Option<ConversionData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub height: u64,
+    pub time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// Price of the basket in one reserve, with the reserve held at each point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReservePoint {
+    #[serde(flatten)]
+    pub price: Candle,
+    pub reserves: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReserveSeries {
+    pub currencyid: String,
+    pub name: Option<String>,
+    pub points: Vec<ReservePoint>,
+}
+
+/// Candles for one traded pair taken directly from `volumepairs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairSeries {
+    pub currency: String,
+    pub convertto: String,
+    pub points: Vec<Candle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupplyPoint {
+    pub height: u64,
+    pub time: u64,
+    pub supply: Amount,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencySeries {
+    pub currency: String,
+    pub currencyid: String,
+    pub start: u64,
+    pub end: u64,
+    pub step: u64,
+    /// Currency volumes are denominated in, when conversion data was requested
+    pub volumecurrency: Option<String>,
+    pub supply: Vec<SupplyPoint>,
+    pub reserves: Vec<ReserveSeries>,
+    pub pairs: Vec<PairSeries>,
+    /// Points served from the cache rather than the daemon
+    pub cached_points: usize,
+}
+
+/// Heights sampled for a `start:end:step` range
+pub fn range_heights(start: u64, end: u64, step: u64) -> Vec<u64> {
+    (start..=end).step_by(step.max(1) as usize).collect()
+}
+
+/// Split sampled heights into `getcurrencystate` range strings of bounded size
+pub fn chunk_ranges(heights: &[u64], step: u64) -> Vec<String> {
+    let mut ranges = Vec::new();
+    let mut run: Vec<u64> = Vec::new();
+
+    for &height in heights {
+        let contiguous = run.last().is_none_or(|last| height == last + step);
+        if !contiguous || run.len() as u64 >= MAX_INTERVALS_PER_CALL {
+            ranges.push(format!("{},{},{}", run[0], run[run.len() - 1], step));
+            run.clear();
+        }
+        run.push(height);
+    }
+    if let Some(first) = run.first() {
+        ranges.push(format!("{},{},{}", first, run[run.len() - 1], step));
+    }
+
+    ranges
+}
+
+/// Turn sampled states into per-reserve, per-pair and supply series
+pub fn build_series(
+    points: &[CurrencyStatePoint],
+    names: &HashMap<String, String>
+) -> (Vec<SupplyPoint>, Vec<ReserveSeries>, Vec<PairSeries>) {
+    let mut supply = Vec::with_capacity(points.len());
+    let mut reserves: Vec<ReserveSeries> = Vec::new();
+    let mut pairs: Vec<PairSeries> = Vec::new();
+
+    for point in points {
+        let state = &point.currencystate;
+        supply.push(SupplyPoint { height: point.height, time: point.blocktime, supply: state.supply });
+
+        let volume_pairs = point.conversiondata.as_ref().map(|c| c.volumepairs.as_slice()).unwrap_or_default();
+
+        for reserve in &state.reservecurrencies {
+            let name = names.get(&reserve.currencyid);
+            let matches = |s: &str| s == reserve.currencyid || Some(s) == name.map(|n| n.as_str());
+            let volume: f64 = volume_pairs.iter()
+                .filter(|p| matches(&p.currency) || matches(&p.convertto))
+                .map(|p| p.volume)
+                .sum();
+
+            let series = match reserves.iter_mut().position(|s| s.currencyid == reserve.currencyid) {
+                Some(index) => &mut reserves[index],
+                None => {
+                    reserves.push(ReserveSeries {
+                        currencyid: reserve.currencyid.clone(),
+                        name: name.cloned(),
+                        points: Vec::new(),
+                    });
+                    reserves.last_mut().unwrap()
+                }
+            };
+
+            // Sampled states only give a price per point, so each candle opens at the previous close
+            let close = reserve.priceinreserve;
+            let open = series.points.last().map(|p| p.price.close).unwrap_or(close);
+            series.points.push(ReservePoint {
+                price: Candle {
+                    height: point.height,
+                    time: point.blocktime,
+                    open,
+                    high: open.max(close),
+                    low: open.min(close),
+                    close,
+                    volume,
+                },
+                reserves: reserve.reserves,
+            });
+        }
+
+        for pair in volume_pairs {
+            let candle = Candle {
+                height: point.height,
+                time: point.blocktime,
+                open: pair.open,
+                high: pair.high,
+                low: pair.low,
+                close: pair.close,
+                volume: pair.volume,
+            };
+            match pairs.iter_mut().find(|s| s.currency == pair.currency && s.convertto == pair.convertto) {
+                Some(series) => series.points.push(candle),
+                None => pairs.push(PairSeries {
+                    currency: pair.currency.clone(),
+                    convertto: pair.convertto.clone(),
+                    points: vec![candle],
+                }),
+            }
+        }
+    }
+
+    (supply, reserves, pairs)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SeriesKey {
+    currencyid: String,
+    conversion: Option<String>,
+    step: u64,
+}
+
+/// Final state points by sampled height, owned by the client so it is scoped to one chain
+///
+/// A cached `None` records a height the daemon returned nothing for.
+#[derive(Debug, Default)]
+pub struct SeriesCache {
+    inner: RwLock<HashMap<SeriesKey, HashMap<u64, Option<CurrencyStatePoint>>>>,
+}
+
+impl SeriesCache {
+    fn get(&self, key: &SeriesKey, height: u64) -> Option<Option<CurrencyStatePoint>> {
+        self.inner.read().ok()?.get(key)?.get(&height).cloned()
+    }
+
+    fn insert(&self, key: &SeriesKey, height: u64, point: Option<CurrencyStatePoint>) {
+        if let Ok(mut inner) = self.inner.write() {
+            inner.entry(key.clone()).or_default().insert(height, point);
+        }
+    }
+}
+
+impl VerusRpcClient {
+    /// Sample a currency's state over `start..=end` every `step` blocks as chartable series
+    pub async fn get_currency_series(
+        &self,
+        currency: &str,
+        start: u64,
+        end: Option<u64>,
+        step: u64,
+        conversion_currency: Option<&str>
+    ) -> Result<CurrencySeries, RpcError> {
+        if step == 0 {
+            return Err(RpcError::ValidationError("Step must be at least 1 block".to_string()));
+        }
+
+        let definition = self.get_currency(currency, None).await?;
+        let tip = self.get_block_count(None).await?;
+        let end = end.unwrap_or(tip).min(tip);
+        if start > end {
+            return Err(RpcError::ValidationError(format!("Start height {} is after end height {}", start, end)));
+        }
+
+        let key = SeriesKey {
+            currencyid: definition.currencyid.clone(),
+            conversion: conversion_currency.map(|c| c.to_lowercase()),
+            step,
+        };
+        let heights = range_heights(start, end, step);
+
+        let mut points: HashMap<u64, Option<CurrencyStatePoint>> = HashMap::new();
+        let mut missing = Vec::new();
+        for &height in &heights {
+            match self.series_cache.get(&key, height) {
+                Some(point) => {
+                    points.insert(height, point);
+                }
+                None => missing.push(height),
+            }
+        }
+        let cached_points = heights.len() - missing.len();
+
+        for range in chunk_ranges(&missing, step) {
+            let mut bounds = range.split(',').filter_map(|s| s.parse::<u64>().ok());
+            let (first, last) = (bounds.next().unwrap_or(start), bounds.next().unwrap_or(end));
+
+            let mut params = vec![json!(definition.currencyid), json!(range)];
+            if let Some(conversion) = conversion_currency {
+                params.push(json!(conversion));
+            }
+            let response: Value = self.call_cached("getcurrencystate", json!(params), last).await?;
+            let mut fetched: HashMap<u64, CurrencyStatePoint> = response.as_array()
+                .ok_or(RpcError::InvalidResponse)?
+                .iter()
+                // The trailing summary entry has no height
+                .filter_map(|entry| serde_json::from_value::<CurrencyStatePoint>(entry.clone()).ok())
+                .map(|point| (point.height, point))
+                .collect();
+
+            for height in range_heights(first, last, step) {
+                let point = fetched.remove(&height);
+                if height + SERIES_FINALITY_DEPTH <= tip {
+                    self.series_cache.insert(&key, height, point.clone());
+                }
+                points.insert(height, point);
+            }
+        }
+
+        let ordered: Vec<CurrencyStatePoint> = heights.iter()
+            .filter_map(|h| points.remove(h).flatten())
+            .collect();
+        let volumecurrency = ordered.iter()
+            .filter_map(|p| p.conversiondata.as_ref())
+            .map(|c| c.volumecurrency.clone())
+            .find(|c| !c.is_empty());
+        let (supply, reserves, pairs) = build_series(&ordered, &definition.currencynames);
+
+        Ok(CurrencySeries {
+            currency: currency.to_string(),
+            currencyid: definition.currencyid,
+            start,
+            end,
+            step,
+            volumecurrency,
+            supply,
+            reserves,
+            pairs,
+            cached_points,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{VRSC, VETH};
+
+    fn point(height: u64, vrsc_price: f64, volume: f64) -> CurrencyStatePoint {
+        serde_json::from_value(json!({
+            "height": height,
+            "blocktime": 1700000000 + height * 60,
+            "currencystate": {
+                "supply": 1000.0 + height as f64,
+                "reservecurrencies": [
+                    { "currencyid": VRSC, "weight": 0.5, "reserves": 500.0, "priceinreserve": vrsc_price },
+                    { "currencyid": VETH, "weight": 0.5, "reserves": 0.2, "priceinreserve": 0.0001 }
+                ]
+            },
+            "conversiondata": {
+                "volumecurrency": "VRSC",
+                "volumethisinterval": volume,
+                "volumepairs": [
+                    { "currency": "VRSC", "convertto": "vETH", "volume": volume, "open": 1.0, "high": 2.0, "low": 0.5, "close": 1.5 }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_chunking() {
+        assert_eq!(range_heights(100, 120, 10), vec![100, 110, 120]);
+        assert_eq!(chunk_ranges(&[100, 110, 130, 140], 10), vec!["100,110,10", "130,140,10"]);
+
+        let heights = range_heights(0, 1200 - 1, 1);
+        let chunks = chunk_ranges(&heights, 1);
+        assert_eq!(chunks, vec!["0,499,1", "500,999,1", "1000,1199,1"]);
+    }
+
+    #[test]
+    fn test_build_series() {
+        let names = HashMap::from([(VRSC.to_string(), "VRSC".to_string()), (VETH.to_string(), "vETH".to_string())]);
+        let (supply, reserves, pairs) = build_series(&[point(100, 2.0, 10.0), point(110, 2.5, 4.0)], &names);
+
+        assert_eq!(supply.len(), 2);
+        assert_eq!(reserves.len(), 2);
+
+        let vrsc = &reserves[0];
+        assert_eq!(vrsc.name.as_deref(), Some("VRSC"));
+        let second = &vrsc.points[1].price;
+        assert_eq!((second.open, second.close, second.high, second.low), (2.0, 2.5, 2.5, 2.0));
+        assert_eq!(second.volume, 4.0);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].points.len(), 2);
+        assert_eq!(pairs[0].points[0].high, 2.0);
+    }
+}
@@ -0,0 +1,362 @@
+// Pre-flight Validation for definecurrency
+use crate::rpc::{VerusRpcClient, RpcError, DefineCurrencyRequest};
+use crate::rpc::amount::Amount;
+use crate::rpc::currency_options::CurrencyOptions;
+use crate::rpc::identity_name::{validate_name_component, IdentityRef};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// The daemon caps ID referral chains at this many levels
+const MAX_ID_REFERRAL_LEVELS: u32 = 5;
+
+/// Maximum number of reserve currencies in a basket
+const MAX_BASKET_CURRENCIES: usize = 10;
+
+const WEIGHT_SUM_TOLERANCE: f64 = 0.000001;
+
+/// A problem with one field of a currency definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Field path such as `weights` or `preallocations[1]`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub(crate) fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+/// Every field error joined into one `ValidationError`, or `Ok` when there are none
+pub(crate) fn field_errors_result(errors: Vec<FieldError>) -> Result<(), RpcError> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = errors.iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect();
+    Err(RpcError::ValidationError(details.join("; ")))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyValidationReport {
+    pub valid: bool,
+    pub errors: Vec<FieldError>,
+}
+
+impl CurrencyValidationReport {
+    pub fn into_result(self) -> Result<(), RpcError> {
+        if self.valid {
+            return Ok(());
+        }
+        field_errors_result(self.errors)
+    }
+}
+
+/// State of the identity that will own the new currency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DefiningIdentity {
+    Controlled,
+    NotControlled,
+    NotFound,
+    /// The parent did not resolve, so the identity could not be looked up
+    Unknown,
+}
+
+/// Chain state the definition is checked against
+#[derive(Debug, Clone)]
+pub struct DefinitionContext {
+    pub height: u64,
+    /// Fully qualified name of the identity the currency is defined on
+    pub identity_name: String,
+    pub identity: DefiningIdentity,
+    /// Why `parent` does not resolve to an identity, if it does not
+    pub parent_error: Option<String>,
+    /// Preallocation recipients that do not resolve to an identity
+    pub unresolved_recipients: HashSet<String>,
+}
+
+/// Check a definition for the mistakes the daemon reports cryptically
+pub fn validate_definition(definition: &DefineCurrencyRequest, context: &DefinitionContext) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let options = definition.options;
+
+    if let Err(e) = validate_name_component(&definition.name) {
+        errors.push(FieldError::new("name", e));
+    }
+    if let Some(e) = &context.parent_error {
+        errors.push(FieldError::new("parent", e.clone()));
+    }
+    match context.identity {
+        DefiningIdentity::Controlled | DefiningIdentity::Unknown => {}
+        DefiningIdentity::NotControlled => errors.push(FieldError::new(
+            "name",
+            format!("Identity {} is not controlled by this wallet", context.identity_name),
+        )),
+        DefiningIdentity::NotFound => errors.push(FieldError::new(
+            "name",
+            format!("Identity {} does not exist; register it before defining the currency", context.identity_name),
+        )),
+    }
+
+    errors.extend(options.errors().into_iter().map(|e| FieldError::new("options", e)));
+
+    let currencies = definition.currencies.as_deref().unwrap_or_default();
+    if options.contains(CurrencyOptions::FRACTIONAL) {
+        validate_basket(definition, currencies, &mut errors);
+    } else if definition.weights.is_some() {
+        errors.push(FieldError::new("weights", "Weights only apply to fractional currencies"));
+    }
+
+    // (field, entry count, any negative entry)
+    let amounts = |values: &Option<Vec<Amount>>| {
+        values.as_ref().map(|v| (v.len(), v.iter().any(|a| a.is_negative())))
+    };
+    let per_currency = [
+        ("conversions", definition.conversions.as_ref().map(|v| (v.len(), v.iter().any(|c| *c < 0.0)))),
+        ("reserves", amounts(&definition.reserves)),
+        ("minpreconversion", amounts(&definition.minpreconversion)),
+        ("maxpreconversion", amounts(&definition.maxpreconversion)),
+        ("initialcontributions", amounts(&definition.initialcontributions)),
+    ];
+    for (field, summary) in per_currency {
+        if let Some((len, any_negative)) = summary {
+            if len != currencies.len() {
+                errors.push(FieldError::new(field, format!(
+                    "Has {} entries but {} currencies are listed", len, currencies.len()
+                )));
+            }
+            if any_negative {
+                errors.push(FieldError::new(field, "Values cannot be negative"));
+            }
+        }
+    }
+
+    for (index, entry) in definition.preallocations.iter().flatten().enumerate() {
+        let field = format!("preallocations[{}]", index);
+        match preallocation_entry(entry) {
+            Some((recipient, amount)) => {
+                if context.unresolved_recipients.contains(recipient) {
+                    errors.push(FieldError::new(&field, format!("{} is not a valid identity", recipient)));
+                }
+                if amount.is_none_or(|a| !a.is_positive()) {
+                    errors.push(FieldError::new(&field, "Amount must be a positive number"));
+                }
+            }
+            None => errors.push(FieldError::new(&field, "Expected an object of the form {\"identity@\": amount}")),
+        }
+    }
+
+    if let Some(start) = definition.startblock {
+        if start <= context.height {
+            errors.push(FieldError::new("startblock", format!(
+                "Start block {} is not after the current height {}", start, context.height
+            )));
+        }
+    }
+    if let (Some(start), Some(end)) = (definition.startblock, definition.endblock) {
+        if end != 0 && end <= start {
+            errors.push(FieldError::new("endblock", "End block must be after the start block"));
+        }
+    }
+
+    let issues_ids = options.contains(CurrencyOptions::ID_RESTRICTED)
+        || options.contains(CurrencyOptions::ID_REFERRALS)
+        || options.contains(CurrencyOptions::ID_STAKING);
+    if issues_ids && definition.idregistrationfees.is_none() {
+        errors.push(FieldError::new("idregistrationfees", "Required for currencies that issue IDs"));
+    }
+    if definition.idreferrallevels.is_some_and(|levels| levels > MAX_ID_REFERRAL_LEVELS) {
+        errors.push(FieldError::new("idreferrallevels", format!("Cannot exceed {}", MAX_ID_REFERRAL_LEVELS)));
+    }
+
+    errors
+}
+
+fn validate_basket(definition: &DefineCurrencyRequest, currencies: &[String], errors: &mut Vec<FieldError>) {
+    if currencies.is_empty() {
+        errors.push(FieldError::new("currencies", "A fractional currency needs at least one reserve currency"));
+        return;
+    }
+    if currencies.len() > MAX_BASKET_CURRENCIES {
+        errors.push(FieldError::new("currencies", format!("A basket can hold at most {} currencies", MAX_BASKET_CURRENCIES)));
+    }
+
+    let mut seen = HashSet::new();
+    for (index, currency) in currencies.iter().enumerate() {
+        if !seen.insert(currency.to_lowercase()) {
+            errors.push(FieldError::new(format!("currencies[{}]", index), format!("{} is listed twice", currency)));
+        }
+    }
+
+    match &definition.weights {
+        None => errors.push(FieldError::new("weights", "Required for fractional currencies")),
+        Some(weights) => {
+            if weights.len() != currencies.len() {
+                errors.push(FieldError::new("weights", format!(
+                    "Has {} entries but {} currencies are listed", weights.len(), currencies.len()
+                )));
+            }
+            if weights.iter().any(|w| *w <= 0.0) {
+                errors.push(FieldError::new("weights", "Weights must be positive"));
+            }
+            let sum: f64 = weights.iter().sum();
+            if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+                errors.push(FieldError::new("weights", format!("Weights must sum to 1 (currently {})", sum)));
+            }
+        }
+    }
+}
+
+/// Split a `{"recipient": amount}` preallocation entry
+fn preallocation_entry(entry: &Value) -> Option<(&str, Option<Amount>)> {
+    let object = entry.as_object().filter(|o| o.len() == 1)?;
+    let (recipient, amount) = object.iter().next()?;
+    Some((recipient.as_str(), Amount::deserialize(amount).ok()))
+}
+
+impl VerusRpcClient {
+    /// Look up the chain state a definition depends on and validate it
+    pub async fn validate_currency_definition(
+        &self,
+        definition: &DefineCurrencyRequest
+    ) -> Result<CurrencyValidationReport, RpcError> {
+        let height = self.get_block_count(None).await?;
+
+        let mut parent_error = None;
+        let identity_name = match definition.parent.as_deref().filter(|p| !p.is_empty()) {
+            Some(parent) => match self.resolve_identity(parent, None).await {
+                Ok(resolved) => format!("{}.{}", definition.name, resolved.fullyqualifiedname),
+                Err(e @ (RpcError::IdentityNotFound(_) | RpcError::InvalidIdentityName(_) | RpcError::InvalidAddress(_))) => {
+                    parent_error = Some(e.to_string());
+                    format!("{}.{}", definition.name, parent)
+                }
+                Err(e) => return Err(e),
+            },
+            None => format!("{}@", definition.name),
+        };
+        let identity = if parent_error.is_some() {
+            DefiningIdentity::Unknown
+        } else {
+            match self.get_identity(&identity_name, None, None, None, None).await {
+                Ok(value) => {
+                    let can_sign = value.get("cansignfor").and_then(|v| v.as_bool()).unwrap_or(false);
+                    if can_sign { DefiningIdentity::Controlled } else { DefiningIdentity::NotControlled }
+                }
+                Err(RpcError::IdentityNotFound(_)) | Err(RpcError::InvalidAddress(_)) => DefiningIdentity::NotFound,
+                Err(e) => return Err(e),
+            }
+        };
+
+        let mut unresolved_recipients = HashSet::new();
+        for (recipient, _) in definition.preallocations.iter().flatten().filter_map(preallocation_entry) {
+            let resolves = match IdentityRef::parse(recipient) {
+                Ok(parsed) => match self.get_identity(&parsed.to_rpc_param(), None, None, None, None).await {
+                    Ok(_) => true,
+                    Err(RpcError::IdentityNotFound(_)) | Err(RpcError::InvalidAddress(_)) => false,
+                    Err(e) => return Err(e),
+                },
+                Err(_) => false,
+            };
+            if !resolves {
+                unresolved_recipients.insert(recipient.to_string());
+            }
+        }
+
+        let context = DefinitionContext { height, identity_name, identity, parent_error, unresolved_recipients };
+        let errors = validate_definition(definition, &context);
+
+        Ok(CurrencyValidationReport {
+            valid: errors.is_empty(),
+            errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> DefinitionContext {
+        DefinitionContext {
+            height: 1000,
+            identity_name: "basket@".to_string(),
+            identity: DefiningIdentity::Controlled,
+            parent_error: None,
+            unresolved_recipients: HashSet::from(["nobody@".to_string()]),
+        }
+    }
+
+    fn definition(value: Value) -> DefineCurrencyRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn fields(errors: &[FieldError]) -> Vec<&str> {
+        errors.iter().map(|e| e.field.as_str()).collect()
+    }
+
+    #[test]
+    fn test_valid_basket() {
+        let basket = definition(json!({
+            "name": "basket",
+            "options": 33,
+            "currencies": ["VRSC", "vETH"],
+            "weights": [0.5, 0.5],
+            "conversions": [1, 1],
+            "initialsupply": 1000,
+            "startblock": 1100,
+            "preallocations": [{ "alice@": 10 }]
+        }));
+        assert_eq!(validate_definition(&basket, &context()), vec![]);
+    }
+
+    #[test]
+    fn test_basket_errors_are_field_addressed() {
+        let basket = definition(json!({
+            "name": "basket",
+            "options": 33 | 8,
+            "currencies": ["VRSC", "vETH", "VRSC"],
+            "weights": [0.5, 0.4],
+            "conversions": [1],
+            "startblock": 900,
+            "preallocations": [{ "alice@": 10 }, { "nobody@": 5 }, ["bad"]]
+        }));
+        let errors = validate_definition(&basket, &context());
+        assert_eq!(
+            fields(&errors),
+            vec![
+                "currencies[2]",
+                "weights",
+                "weights",
+                "conversions",
+                "preallocations[1]",
+                "preallocations[2]",
+                "startblock",
+                "idregistrationfees",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identity_and_token_rules() {
+        let token = definition(json!({
+            "name": "token",
+            "options": 32,
+            "weights": [1.0]
+        }));
+        let mut context = context();
+        context.identity = DefiningIdentity::NotControlled;
+
+        let errors = validate_definition(&token, &context);
+        assert_eq!(fields(&errors), vec!["name", "weights"]);
+
+        // An unresolved parent is reported on `parent` rather than as a missing identity
+        context.identity = DefiningIdentity::Unknown;
+        context.parent_error = Some("Identity not found: nowhere@".to_string());
+        let errors = validate_definition(&token, &context);
+        assert_eq!(fields(&errors), vec!["parent", "weights"]);
+    }
+}
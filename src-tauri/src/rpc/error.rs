@@ -31,6 +31,9 @@ pub enum RpcError {
     
     #[error("Identity not found: {0}")]
     IdentityNotFound(String),
+
+    #[error("Invalid identity name: {0}")]
+    InvalidIdentityName(String),
     
     #[error("Currency not found: {0}")]
     CurrencyNotFound(String),
@@ -127,6 +130,9 @@ impl RpcError {
             RpcError::IdentityNotFound(name) => {
                 format!("Identity '{}' was not found. Please verify the identity name.", name)
             }
+            RpcError::InvalidIdentityName(details) => {
+                format!("Invalid identity name: {}. Use the form name@ or name.parent@, or an i-address.", details)
+            }
             RpcError::CurrencyNotFound(name) => {
                 format!("Currency '{}' was not found. Please verify the currency name.", name)
             }
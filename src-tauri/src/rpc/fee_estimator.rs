@@ -0,0 +1,318 @@
+// Send Fee Estimation
+use crate::rpc::{VerusRpcClient, RpcError, SendCurrencyRequest};
+use crate::rpc::amount::Amount;
+use crate::rpc::conversion_routes::parse_estimate;
+use crate::rpc::send_builder::SendOutput;
+use crate::rpc::tx_inspector::add_value;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Network fee `sendcurrency` pays when none is given
+pub const DEFAULT_TRANSACTION_FEE: Amount = Amount::from_sats(10_000);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeEstimateRequest {
+    pub from_address: String,
+    pub outputs: Vec<SendOutput>,
+    #[serde(default)]
+    pub min_conf: Option<u32>,
+    #[serde(default)]
+    pub fee_amount: Option<Amount>,
+}
+
+/// Fees of one send, each keyed by currency i-address
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeBreakdown {
+    /// Paid to miners in the native currency
+    pub network: BTreeMap<String, Amount>,
+    /// Taken out of the converted amount by the basket, or charged on top with `addconversionfees`
+    pub conversion: BTreeMap<String, Amount>,
+    /// Carried by reserve transfers to pay for their export and import
+    pub export: BTreeMap<String, Amount>,
+    pub total: BTreeMap<String, Amount>,
+}
+
+/// The send simulated with every export paying its fee in one currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeCurrencyOption {
+    pub currencyid: String,
+    pub name: String,
+    /// Export fee in this currency
+    pub export_fee: Option<Amount>,
+    /// Whether the source holds everything the send needs; `None` when its balance is unknown
+    pub affordable: Option<bool>,
+    /// Why the daemon refused the send with this fee currency
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    pub fees: FeeBreakdown,
+    /// Everything the send takes from the source, fees included
+    pub required: BTreeMap<String, Amount>,
+    /// Only for sends with an export
+    pub fee_currency_options: Vec<FeeCurrencyOption>,
+    pub recommended_fee_currency: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Serialized transaction from a `sendcurrency` template, which is a bare hex string
+/// on some daemon versions and an object with `hextx` on others
+pub fn template_hex(result: &Value) -> Option<&str> {
+    result.as_str().or_else(|| result.get("hextx").and_then(|h| h.as_str()))
+}
+
+/// Fees carried by every reserve transfer of a decoded transaction, by fee currency
+pub fn transfer_fees(decoded: &Value) -> BTreeMap<String, Amount> {
+    let mut fees = BTreeMap::new();
+    let transfers = decoded.get("vout").and_then(|v| v.as_array()).into_iter().flatten()
+        .filter_map(|output| output.get("scriptPubKey")?.get("reservetransfer"));
+    for transfer in transfers {
+        let currency = transfer.get("feecurrencyid").and_then(|f| f.as_str());
+        let amount = transfer.get("fees").and_then(|f| Amount::deserialize(f).ok());
+        if let (Some(currency), Some(amount)) = (currency, amount) {
+            if amount.is_positive() {
+                add_value(&mut fees, currency, amount);
+            }
+        }
+    }
+    fees
+}
+
+/// Everything a send takes from its source: the requested amounts plus network and transfer fees
+///
+/// `requested` includes conversion fees charged on top of an amount. The template's outputs
+/// are not summed, since they also return change to the source.
+fn required_funds(
+    requested: &BTreeMap<String, Amount>,
+    network_fee: Amount,
+    decoded: &Value,
+    native_id: &str
+) -> BTreeMap<String, Amount> {
+    let mut required = requested.clone();
+    add_value(&mut required, native_id, network_fee);
+    for (currency, fee) in transfer_fees(decoded) {
+        add_value(&mut required, &currency, fee);
+    }
+    required
+}
+
+pub fn fee_breakdown(
+    network: BTreeMap<String, Amount>,
+    conversion: BTreeMap<String, Amount>,
+    export: BTreeMap<String, Amount>
+) -> FeeBreakdown {
+    let mut total = BTreeMap::new();
+    for (currency, amount) in network.iter().chain(&conversion).chain(&export) {
+        add_value(&mut total, currency, *amount);
+    }
+    FeeBreakdown { network, conversion, export, total }
+}
+
+/// Whether `balances` covers every currency in `required`
+pub fn covers(balances: &BTreeMap<String, Amount>, required: &BTreeMap<String, Amount>) -> bool {
+    required.iter().all(|(currency, amount)| balances.get(currency).copied().unwrap_or(Amount::ZERO) >= *amount)
+}
+
+/// Affordable options first, preferring the native currency so destination funds are left alone
+pub fn recommend_fee_currency(options: &[FeeCurrencyOption], native_id: &str) -> Option<String> {
+    let usable = |o: &&FeeCurrencyOption| o.error.is_none();
+    let affordable = |o: &&FeeCurrencyOption| o.affordable != Some(false);
+    options.iter().filter(usable).filter(affordable).find(|o| o.currencyid == native_id)
+        .or_else(|| options.iter().filter(usable).find(affordable))
+        .or_else(|| options.iter().find(usable))
+        .map(|o| o.currencyid.clone())
+}
+
+/// A simulated send: its decoded template and network fee
+struct SimulatedSend {
+    decoded: Value,
+    network_fee: Amount,
+}
+
+impl VerusRpcClient {
+    async fn simulate_send(
+        &self,
+        request: &FeeEstimateRequest,
+        outputs: Vec<SendCurrencyRequest>
+    ) -> Result<SimulatedSend, RpcError> {
+        let template = self.send_currency_template(&request.from_address, outputs, request.min_conf, request.fee_amount).await?;
+        let hex = template_hex(&template).ok_or(RpcError::InvalidResponse)?;
+        let network_fee = template.get("feeamount")
+            .and_then(|f| Amount::deserialize(f).ok())
+            .or(request.fee_amount)
+            .unwrap_or(DEFAULT_TRANSACTION_FEE);
+        Ok(SimulatedSend { decoded: self.decode_raw_transaction(hex).await?, network_fee })
+    }
+
+    /// Balances of the source address, or `None` when it cannot be queried (e.g. `*`)
+    async fn source_balances(&self, request: &FeeEstimateRequest) -> Option<BTreeMap<String, Amount>> {
+        let minconf = request.min_conf.unwrap_or(1);
+        let balances = self.get_currency_balance(&request.from_address, Some(minconf), Some(false), None, None).await.ok()?;
+        Some(balances.as_object()?.iter()
+            .filter_map(|(id, amount)| Some((id.clone(), Amount::deserialize(amount).ok()?)))
+            .collect())
+    }
+
+    /// Simulate a send and break down what it will cost, recommending a fee currency for exports
+    pub async fn estimate_send_fees(&self, request: &FeeEstimateRequest) -> Result<FeeEstimate, RpcError> {
+        let native_id = self.get_info(None).await?.chainid.unwrap_or_default();
+        let outputs: Vec<SendCurrencyRequest> = request.outputs.iter().map(SendOutput::to_request).collect();
+        let mut warnings = Vec::new();
+
+        let mut requested = BTreeMap::new();
+        let mut currency_ids = Vec::new();
+        for output in &outputs {
+            let currency_id = match output.currency.as_deref() {
+                Some(currency) => self.get_currency(currency, None).await?.currencyid,
+                None => native_id.clone(),
+            };
+            add_value(&mut requested, &currency_id, output.amount);
+            currency_ids.push(currency_id);
+        }
+
+        let mut conversion = BTreeMap::new();
+        for (output, currency_id) in outputs.iter().zip(&currency_ids).filter(|(o, _)| o.convertto.is_some()) {
+            let (Some(currency), Some(convert_to)) = (output.currency.as_deref(), output.convertto.as_deref()) else { continue };
+            let estimate = self.estimate_conversion(currency, output.amount, convert_to, output.via.as_deref()).await
+                .and_then(|value| parse_estimate(&value).ok_or(RpcError::InvalidResponse));
+            match estimate {
+                Ok(estimate) => {
+                    let fee = output.amount.checked_sub(estimate.net_input.unwrap_or(output.amount))
+                        .filter(|f| f.is_positive())
+                        .unwrap_or(Amount::ZERO);
+                    add_value(&mut conversion, currency_id, fee);
+                    // Charged on top of the amount rather than out of it
+                    if output.addconversionfees == Some(true) {
+                        add_value(&mut requested, currency_id, fee);
+                    }
+                }
+                Err(e) => warnings.push(format!("Could not estimate conversion of {} to {}: {}", currency, convert_to, e)),
+            }
+        }
+
+        let send = self.simulate_send(request, outputs.clone()).await?;
+        let required = required_funds(&requested, send.network_fee, &send.decoded, &native_id);
+        let fees = fee_breakdown(
+            BTreeMap::from([(native_id.clone(), send.network_fee)]),
+            conversion,
+            transfer_fees(&send.decoded),
+        );
+
+        let balances = self.source_balances(request).await;
+        if let Some(balances) = &balances {
+            if !covers(balances, &required) {
+                warnings.push("Source does not hold enough to cover the send and its fees".to_string());
+            }
+        }
+
+        // Exports can pay fees in this chain's currency or the destination system's
+        let mut candidates = vec![native_id.clone()];
+        for system in outputs.iter().filter_map(|o| o.exportto.as_deref()) {
+            let currencyid = self.get_currency(system, None).await?.currencyid;
+            if !candidates.contains(&currencyid) {
+                candidates.push(currencyid);
+            }
+        }
+        let mut fee_currency_options = Vec::new();
+        if outputs.iter().any(|o| o.exportto.is_some()) {
+            for currencyid in candidates {
+                let name = self.get_currency(&currencyid, None).await.map(|c| c.name).unwrap_or_else(|_| currencyid.clone());
+                let with_fee_currency: Vec<SendCurrencyRequest> = outputs.iter()
+                    .map(|o| if o.exportto.is_some() { o.clone().fee_currency(&currencyid) } else { o.clone() })
+                    .collect();
+                let option = match self.simulate_send(request, with_fee_currency).await {
+                    Ok(simulated) => {
+                        let needed = required_funds(&requested, simulated.network_fee, &simulated.decoded, &native_id);
+                        FeeCurrencyOption {
+                            export_fee: transfer_fees(&simulated.decoded).get(&currencyid).copied(),
+                            affordable: balances.as_ref().map(|b| covers(b, &needed)),
+                            error: None,
+                            currencyid,
+                            name,
+                        }
+                    }
+                    Err(e) => FeeCurrencyOption { currencyid, name, export_fee: None, affordable: None, error: Some(e.to_string()) },
+                };
+                fee_currency_options.push(option);
+            }
+        }
+
+        Ok(FeeEstimate {
+            recommended_fee_currency: recommend_fee_currency(&fee_currency_options, &native_id),
+            fees,
+            required,
+            fee_currency_options,
+            warnings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{amount, VRSC, VETH, TBTC};
+    use serde_json::json;
+
+    fn option(currencyid: &str, affordable: Option<bool>, error: Option<&str>) -> FeeCurrencyOption {
+        FeeCurrencyOption {
+            currencyid: currencyid.to_string(),
+            name: currencyid.to_string(),
+            export_fee: None,
+            affordable,
+            error: error.map(|e| e.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_transfer_fees_and_required() {
+        let decoded = json!({ "vout": [
+            { "n": 0, "value": 0.0, "scriptPubKey": { "reservetransfer": {
+                "currencyvalues": { TBTC: 1.0 }, "feecurrencyid": VETH, "fees": 0.003 } } },
+            { "n": 1, "value": 0.0, "scriptPubKey": { "reservetransfer": {
+                "currencyvalues": { TBTC: 0.5 }, "feecurrencyid": VETH, "fees": 0.002 } } },
+            // Change back to the source
+            { "n": 2, "value": 4.0, "scriptPubKey": { "type": "pubkeyhash" } },
+        ] });
+        assert_eq!(transfer_fees(&decoded), BTreeMap::from([(VETH.to_string(), amount("0.005"))]));
+
+        let requested = BTreeMap::from([(TBTC.to_string(), amount("1.5"))]);
+        let required = required_funds(&requested, amount("0.0001"), &decoded, VRSC);
+        assert_eq!(required.get(VRSC), Some(&amount("0.0001")));
+        assert_eq!(required.get(TBTC), Some(&amount("1.5")));
+        assert_eq!(required.get(VETH), Some(&amount("0.005")));
+
+        let fees = fee_breakdown(
+            BTreeMap::from([(VRSC.to_string(), amount("0.0001"))]),
+            BTreeMap::from([(VRSC.to_string(), amount("0.00025"))]),
+            transfer_fees(&decoded),
+        );
+        assert_eq!(fees.total.get(VRSC), Some(&amount("0.00035")));
+        assert_eq!(fees.total.get(VETH), Some(&amount("0.005")));
+    }
+
+    #[test]
+    fn test_template_hex() {
+        assert_eq!(template_hex(&json!("0400008085")), Some("0400008085"));
+        assert_eq!(template_hex(&json!({ "hextx": "0400008085", "feeamount": 0.0001 })), Some("0400008085"));
+        assert_eq!(template_hex(&json!({})), None);
+    }
+
+    #[test]
+    fn test_recommend_fee_currency() {
+        let options = [option(VRSC, Some(true), None), option(VETH, Some(true), None)];
+        assert_eq!(recommend_fee_currency(&options, VRSC).as_deref(), Some(VRSC));
+
+        let options = [option(VRSC, Some(false), None), option(VETH, Some(true), None)];
+        assert_eq!(recommend_fee_currency(&options, VRSC).as_deref(), Some(VETH));
+
+        let options = [option(VRSC, None, Some("refused")), option(VETH, Some(false), None)];
+        assert_eq!(recommend_fee_currency(&options, VRSC).as_deref(), Some(VETH));
+        assert_eq!(recommend_fee_currency(&[option(VRSC, None, Some("refused"))], VRSC), None);
+
+        let balances = BTreeMap::from([(VRSC.to_string(), amount("2"))]);
+        assert!(covers(&balances, &BTreeMap::from([(VRSC.to_string(), amount("2"))])));
+        assert!(!covers(&balances, &BTreeMap::from([(VETH.to_string(), amount("0.1"))])));
+    }
+}
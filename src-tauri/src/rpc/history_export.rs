@@ -0,0 +1,417 @@
+// Wallet History Export
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::amount::Amount;
+use crate::rpc::wallet_history::{HistoryFilter, TxCategory, WalletTransaction, MAX_HISTORY_PAGE};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Date and block bounds, all inclusive; unset bounds are open
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportRange {
+    /// Unix time
+    #[serde(default)]
+    pub from_time: Option<u64>,
+    #[serde(default)]
+    pub to_time: Option<u64>,
+    #[serde(default)]
+    pub from_height: Option<u64>,
+    #[serde(default)]
+    pub to_height: Option<u64>,
+}
+
+impl ExportRange {
+    /// Unconfirmed entries have no height and only match when no block bound is set
+    pub fn contains(&self, time: u64, height: Option<u64>) -> bool {
+        let in_time = self.from_time.is_none_or(|from| time >= from) && self.to_time.is_none_or(|to| time <= to);
+        let in_blocks = match height {
+            Some(h) => self.from_height.is_none_or(|from| h >= from) && self.to_height.is_none_or(|to| h <= to),
+            None => self.from_height.is_none() && self.to_height.is_none(),
+        };
+        in_time && in_blocks
+    }
+
+    /// Whether an entry is older than the start of the range
+    fn before(&self, time: u64, height: Option<u64>) -> bool {
+        self.from_time.is_some_and(|from| time < from)
+            || height.zip(self.from_height).is_some_and(|(h, from)| h < from)
+    }
+
+    /// History filter for the end of the range, so later entries are never decoded
+    fn end_filter(&self, tip: u64) -> HistoryFilter {
+        HistoryFilter {
+            to_time: self.to_time,
+            // Mined at or below `to_height`; a block bound also rules out unconfirmed entries
+            min_confirmations: self.to_height.map(|to| (tip + 1).saturating_sub(to).max(1) as i64),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportRequest {
+    #[serde(default)]
+    pub range: ExportRange,
+    /// Defaults to the user's downloads folder
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// File name without extension
+    #[serde(default)]
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportLeg {
+    Transfer,
+    /// One source currency of a conversion; a conversion spending several currencies has a row each
+    Conversion,
+}
+
+/// One line of the export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportRow {
+    pub txid: String,
+    pub height: Option<u64>,
+    pub time: u64,
+    /// UTC, ISO 8601
+    pub date: String,
+    pub category: TxCategory,
+    pub leg: ExportLeg,
+    pub address: Option<String>,
+    pub counterparty: Option<String>,
+    pub convert_to: Option<String>,
+    /// Only on the first row of a transaction, so fees are not counted twice
+    pub fee: Option<Amount>,
+    /// Keyed by currency i-address, negative when leaving the wallet
+    pub amounts: BTreeMap<String, Amount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportCurrency {
+    pub currencyid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResult {
+    pub csv_path: String,
+    pub json_path: String,
+    /// Distinct transactions, each of which may span several rows
+    pub transactions: usize,
+    pub rows: usize,
+    /// Currency columns, in column order
+    pub currencies: Vec<ExportCurrency>,
+}
+
+#[derive(Serialize)]
+struct ExportDocument<'a> {
+    range: &'a ExportRange,
+    tip: u64,
+    currencies: &'a [ExportCurrency],
+    rows: &'a [ExportRow],
+}
+
+/// Block a transaction was mined in, from its confirmations at `tip`
+fn block_height(confirmations: i64, tip: u64) -> Option<u64> {
+    (confirmations > 0).then(|| (tip + 1).saturating_sub(confirmations as u64))
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` for a Unix time
+pub fn format_utc(time: u64) -> String {
+    let days = (time / 86_400) as i64;
+    let seconds = time % 86_400;
+    // Civil-from-days over 400-year eras, starting each year in March
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, seconds / 3_600, seconds % 3_600 / 60, seconds % 60
+    )
+}
+
+/// Rows for one wallet entry, with conversions split into a row per source currency
+pub fn export_rows(tx: &WalletTransaction, native_id: &str, tip: u64) -> Vec<ExportRow> {
+    let mut amounts = tx.currencyvalues.clone();
+    if !tx.amount.is_zero() {
+        amounts.insert(native_id.to_string(), tx.amount);
+    }
+    let time = tx.blocktime.unwrap_or(tx.time);
+
+    let row = |leg, amounts| ExportRow {
+        txid: tx.txid.clone(),
+        height: block_height(tx.confirmations, tip),
+        time,
+        date: format_utc(time),
+        category: tx.category,
+        leg,
+        address: tx.address.clone(),
+        counterparty: tx.counterparty.clone(),
+        convert_to: tx.convert_to.clone(),
+        fee: None,
+        amounts,
+    };
+
+    let mut rows: Vec<ExportRow> = if tx.category == TxCategory::Conversion && amounts.len() > 1 {
+        amounts.into_iter()
+            .map(|(currency, value)| row(ExportLeg::Conversion, BTreeMap::from([(currency, value)])))
+            .collect()
+    } else if tx.category == TxCategory::Conversion {
+        vec![row(ExportLeg::Conversion, amounts)]
+    } else {
+        vec![row(ExportLeg::Transfer, amounts)]
+    };
+    if let Some(first) = rows.first_mut() {
+        first.fee = tx.fee;
+    }
+    rows
+}
+
+/// Leave each transaction's fee on its first row only
+///
+/// `listtransactions` has an entry per wallet output and repeats the whole fee on
+/// each, so a send to several recipients would otherwise count it once per recipient.
+fn keep_first_fees(rows: &mut [ExportRow]) {
+    let mut charged = HashSet::new();
+    for row in rows.iter_mut().filter(|r| r.fee.is_some()) {
+        if !charged.insert(row.txid.clone()) {
+            row.fee = None;
+        }
+    }
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSV with one column per currency after the fixed columns
+pub fn to_csv(rows: &[ExportRow], currencies: &[ExportCurrency]) -> String {
+    let mut header: Vec<String> = ["date", "time", "height", "txid", "category", "leg", "address", "counterparty", "convert_to", "fee"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    header.extend(currencies.iter().map(|c| csv_field(&c.name)));
+
+    let mut csv = header.join(",");
+    csv.push('\n');
+    for row in rows {
+        let category = serde_json::to_value(row.category).ok()
+            .and_then(|c| c.as_str().map(|c| c.to_string()))
+            .unwrap_or_default();
+        let leg = match row.leg {
+            ExportLeg::Transfer => "transfer",
+            ExportLeg::Conversion => "conversion",
+        };
+        let mut fields = vec![
+            row.date.clone(),
+            row.time.to_string(),
+            row.height.map(|h| h.to_string()).unwrap_or_default(),
+            row.txid.clone(),
+            category,
+            leg.to_string(),
+            row.address.clone().unwrap_or_default(),
+            row.counterparty.clone().unwrap_or_default(),
+            row.convert_to.clone().unwrap_or_default(),
+            row.fee.map(|f| f.to_string()).unwrap_or_default(),
+        ];
+        fields.extend(currencies.iter().map(|c| {
+            row.amounts.get(&c.currencyid).map(|a| a.to_string()).unwrap_or_default()
+        }));
+
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), RpcError> {
+    fs::write(path, contents)
+        .map_err(|e| RpcError::Configuration(format!("Cannot write export {}: {}", path.display(), e)))
+}
+
+fn export_directory(directory: Option<&str>) -> Result<PathBuf, RpcError> {
+    match directory.filter(|d| !d.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => dirs::download_dir()
+            .or_else(dirs::document_dir)
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| RpcError::Configuration("No folder to export to".to_string())),
+    }
+}
+
+impl VerusRpcClient {
+    /// Write the wallet's history within a date or block range to CSV and JSON files
+    pub async fn export_transactions(&self, request: &ExportRequest) -> Result<ExportResult, RpcError> {
+        let info = self.get_info(None).await?;
+        let native_id = info.chainid.unwrap_or_default();
+        let tip = self.get_block_count(None).await?;
+        let range = &request.range;
+
+        let filter = range.end_filter(tip);
+        let mut transactions = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let page = self.get_wallet_history(cursor, MAX_HISTORY_PAGE, &filter).await?;
+            let mut all_before = !page.entries.is_empty();
+            for tx in &page.entries {
+                let time = tx.blocktime.unwrap_or(tx.time);
+                let height = block_height(tx.confirmations, tip);
+                if !range.before(time, height) {
+                    all_before = false;
+                }
+                if range.contains(time, height) {
+                    transactions.push(export_rows(tx, &native_id, tip));
+                }
+            }
+            cursor = page.cursor;
+            // Newest first, so a page entirely before the range ends the scan
+            if !page.has_more || all_before {
+                break;
+            }
+        }
+        // Oldest first reads naturally in a ledger
+        let mut rows: Vec<ExportRow> = transactions.into_iter().rev().flatten().collect();
+        keep_first_fees(&mut rows);
+        let transaction_count = rows.iter().map(|r| &r.txid).collect::<HashSet<_>>().len();
+
+        let ids: BTreeSet<&String> = rows.iter().flat_map(|r| r.amounts.keys()).collect();
+        let mut currencies = Vec::new();
+        for id in ids {
+            let name = match self.get_currency(id, None).await {
+                Ok(currency) => currency.name,
+                Err(_) => id.clone(),
+            };
+            currencies.push(ExportCurrency { currencyid: id.clone(), name });
+        }
+        // Native currency first, the rest by name
+        currencies.sort_by(|a, b| (a.currencyid != native_id, &a.name).cmp(&(b.currencyid != native_id, &b.name)));
+
+        let directory = export_directory(request.directory.as_deref())?;
+        fs::create_dir_all(&directory)
+            .map_err(|e| RpcError::Configuration(format!("Cannot create export folder {}: {}", directory.display(), e)))?;
+        let file_name = match request.file_name.as_deref().filter(|f| !f.is_empty()) {
+            Some(name) => name.to_string(),
+            None => format!("{}-history-{}", info.name.as_deref().unwrap_or("wallet").to_lowercase(), tip),
+        };
+        let csv_path = directory.join(format!("{}.csv", file_name));
+        let json_path = directory.join(format!("{}.json", file_name));
+
+        write_file(&csv_path, &to_csv(&rows, &currencies))?;
+        let document = ExportDocument { range, tip, currencies: &currencies, rows: &rows };
+        write_file(&json_path, &serde_json::to_string_pretty(&document)?)?;
+
+        Ok(ExportResult {
+            csv_path: csv_path.display().to_string(),
+            json_path: json_path.display().to_string(),
+            transactions: transaction_count,
+            rows: rows.len(),
+            currencies,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{amount, VRSC, TBTC};
+
+    fn conversion() -> WalletTransaction {
+        WalletTransaction {
+            txid: "t1".to_string(),
+            category: TxCategory::Conversion,
+            wallet_category: "send".to_string(),
+            address: Some("RAddress".to_string()),
+            counterparty: Some("bridge.vETH".to_string()),
+            convert_to: Some("bridge.vETH".to_string()),
+            vout: Some(0),
+            amount: amount("-2"),
+            fee: Some(amount("-0.0001")),
+            currencyvalues: BTreeMap::from([(TBTC.to_string(), amount("-0.5"))]),
+            confirmations: 10,
+            blockhash: None,
+            blocktime: Some(1_700_000_000),
+            time: 1_699_999_990,
+        }
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(format_utc(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_range() {
+        let range = ExportRange { from_height: Some(100), to_height: Some(200), ..Default::default() };
+        assert!(range.contains(0, Some(150)));
+        assert!(!range.contains(0, Some(201)));
+        assert!(!range.contains(0, None));
+        assert!(range.before(0, Some(99)));
+        let filter = range.end_filter(250);
+        assert_eq!(filter.min_confirmations, Some(51));
+        assert_eq!(block_height(51, 250), Some(200));
+
+        let dates = ExportRange { from_time: Some(1000), ..Default::default() };
+        assert!(dates.contains(1000, None));
+        assert!(dates.before(999, Some(5)));
+    }
+
+    #[test]
+    fn test_conversion_legs_to_csv() {
+        let rows = export_rows(&conversion(), VRSC, 1009);
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|r| r.leg == ExportLeg::Conversion && r.height == Some(1000)));
+        assert_eq!(rows[0].fee, Some(amount("-0.0001")));
+        assert_eq!(rows[1].fee, None);
+
+        let currencies = vec![
+            ExportCurrency { currencyid: VRSC.to_string(), name: "VRSC".to_string() },
+            ExportCurrency { currencyid: TBTC.to_string(), name: "tBTC, \"test\"".to_string() },
+        ];
+        let csv = to_csv(&rows, &currencies);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "date,time,height,txid,category,leg,address,counterparty,convert_to,fee,VRSC,\"tBTC, \"\"test\"\"\""
+        );
+        assert_eq!(
+            lines[1],
+            "2023-11-14T22:13:20Z,1700000000,1000,t1,conversion,conversion,RAddress,bridge.vETH,bridge.vETH,-0.00010000,-2.00000000,"
+        );
+        assert!(lines[2].ends_with(",,-0.50000000"));
+    }
+
+    #[test]
+    fn test_fee_charged_once_per_transaction() {
+        let send = |address: &str| WalletTransaction {
+            category: TxCategory::Send,
+            address: Some(address.to_string()),
+            counterparty: Some(address.to_string()),
+            convert_to: None,
+            currencyvalues: BTreeMap::new(),
+            ..conversion()
+        };
+        let mut rows: Vec<ExportRow> = [send("RBob"), send("RCarol")].iter()
+            .flat_map(|tx| export_rows(tx, VRSC, 1009))
+            .collect();
+        keep_first_fees(&mut rows);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].fee, Some(amount("-0.0001")));
+        assert_eq!(rows[1].fee, None);
+    }
+}
@@ -0,0 +1,324 @@
+// Revocation/Recovery Authority Health Check
+use crate::rpc::{VerusRpcClient, RpcError, Identity};
+use crate::rpc::identity_history::{IDENTITY_FLAG_LOCKED, IDENTITY_FLAG_REVOKED};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditCode {
+    IdentityRevoked,
+    SelfRevocationAndRecovery,
+    SelfRevocation,
+    SelfRecovery,
+    AuthorityRevoked,
+    AuthorityNotFound,
+    AuthorityNotControlled,
+    TimelockExpired,
+    TimelockActive,
+    UnlockDelay,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditFinding {
+    pub severity: AuditSeverity,
+    pub code: AuditCode,
+    pub message: String,
+    pub authority: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityAudit {
+    pub name: String,
+    pub identityaddress: String,
+    pub revocationauthority: String,
+    pub recoveryauthority: String,
+    /// Highest severity among the findings, `None` when the identity is healthy
+    pub severity: Option<AuditSeverity>,
+    pub findings: Vec<AuditFinding>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditSummary {
+    pub critical: usize,
+    pub warning: usize,
+    pub info: usize,
+    pub healthy: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorityAuditReport {
+    pub height: u64,
+    pub summary: AuditSummary,
+    pub identities: Vec<IdentityAudit>,
+}
+
+/// What the wallet knows about an authority identity
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthorityStatus {
+    Found { name: String, revoked: bool, controlled: bool },
+    NotFound,
+}
+
+/// Audit a single identity given the looked-up state of its authorities
+pub fn audit_identity(
+    identity: &Identity,
+    authorities: &HashMap<String, AuthorityStatus>,
+    height: u64
+) -> IdentityAudit {
+    let details = &identity.identity;
+    let own_id = &details.identityaddress;
+    let mut findings = Vec::new();
+
+    if details.flags & IDENTITY_FLAG_REVOKED != 0 {
+        findings.push(AuditFinding {
+            severity: AuditSeverity::Critical,
+            code: AuditCode::IdentityRevoked,
+            message: "Identity is revoked and must be recovered by its recovery authority".to_string(),
+            authority: None,
+        });
+    }
+
+    let self_revocation = details.revocationauthority == *own_id;
+    let self_recovery = details.recoveryauthority == *own_id;
+
+    if self_revocation && self_recovery {
+        findings.push(AuditFinding {
+            severity: AuditSeverity::Critical,
+            code: AuditCode::SelfRevocationAndRecovery,
+            message: "Revocation and recovery authority are the identity itself; a compromised key cannot be revoked or recovered".to_string(),
+            authority: Some(own_id.clone()),
+        });
+    } else if self_revocation {
+        findings.push(AuditFinding {
+            severity: AuditSeverity::Warning,
+            code: AuditCode::SelfRevocation,
+            message: "Revocation authority is the identity itself".to_string(),
+            authority: Some(own_id.clone()),
+        });
+    } else if self_recovery {
+        findings.push(AuditFinding {
+            severity: AuditSeverity::Warning,
+            code: AuditCode::SelfRecovery,
+            message: "Recovery authority is the identity itself".to_string(),
+            authority: Some(own_id.clone()),
+        });
+    }
+
+    let mut external_authorities: Vec<(&str, &String)> = Vec::new();
+    if !self_revocation {
+        external_authorities.push(("Revocation", &details.revocationauthority));
+    }
+    if !self_recovery {
+        external_authorities.push(("Recovery", &details.recoveryauthority));
+    }
+
+    for (role, authority) in external_authorities {
+        match authorities.get(authority) {
+            Some(AuthorityStatus::Found { name, revoked, controlled }) => {
+                if *revoked {
+                    findings.push(AuditFinding {
+                        severity: AuditSeverity::Critical,
+                        code: AuditCode::AuthorityRevoked,
+                        message: format!("{} authority {} is revoked and cannot act", role, name),
+                        authority: Some(authority.clone()),
+                    });
+                }
+                if !*controlled {
+                    findings.push(AuditFinding {
+                        severity: AuditSeverity::Info,
+                        code: AuditCode::AuthorityNotControlled,
+                        message: format!("{} authority {} is not controllable by this wallet", role, name),
+                        authority: Some(authority.clone()),
+                    });
+                }
+            }
+            Some(AuthorityStatus::NotFound) | None => {
+                findings.push(AuditFinding {
+                    severity: AuditSeverity::Critical,
+                    code: AuditCode::AuthorityNotFound,
+                    message: format!("{} authority {} could not be found", role, authority),
+                    authority: Some(authority.clone()),
+                });
+            }
+        }
+    }
+
+    // With FLAG_LOCKED the timelock is an unlock delay in blocks, otherwise an absolute height
+    if details.flags & IDENTITY_FLAG_LOCKED != 0 {
+        findings.push(AuditFinding {
+            severity: AuditSeverity::Info,
+            code: AuditCode::UnlockDelay,
+            message: format!("Identity is locked with an unlock delay of {} blocks", details.timelock),
+            authority: None,
+        });
+    } else if details.timelock > 0 {
+        if details.timelock <= height {
+            findings.push(AuditFinding {
+                severity: AuditSeverity::Warning,
+                code: AuditCode::TimelockExpired,
+                message: format!("Timelock expired at block {} and no longer protects this identity", details.timelock),
+                authority: None,
+            });
+        } else {
+            findings.push(AuditFinding {
+                severity: AuditSeverity::Info,
+                code: AuditCode::TimelockActive,
+                message: format!("Timelocked until block {} ({} blocks remaining)", details.timelock, details.timelock - height),
+                authority: None,
+            });
+        }
+    }
+
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+
+    IdentityAudit {
+        name: details.name.clone(),
+        identityaddress: own_id.clone(),
+        revocationauthority: details.revocationauthority.clone(),
+        recoveryauthority: details.recoveryauthority.clone(),
+        severity: findings.iter().map(|f| f.severity).max(),
+        findings,
+    }
+}
+
+impl VerusRpcClient {
+    /// Audit revocation/recovery configuration of every identity in the wallet
+    pub async fn audit_identity_authorities(
+        &self,
+        chain: Option<&str>
+    ) -> Result<AuthorityAuditReport, RpcError> {
+        let height = self.get_block_count(chain).await?;
+        let identities: Vec<Identity> = serde_json::from_value(
+            self.list_identities(None, None, None, chain).await?
+        )?;
+
+        // Look up each distinct external authority once
+        let mut authorities: HashMap<String, AuthorityStatus> = HashMap::new();
+        for identity in &identities {
+            let details = &identity.identity;
+            for authority in [&details.revocationauthority, &details.recoveryauthority] {
+                if *authority == details.identityaddress || authorities.contains_key(authority) {
+                    continue;
+                }
+
+                let status = match self.get_identity(authority, None, None, None, chain).await {
+                    Ok(value) => match serde_json::from_value::<Identity>(value) {
+                        Ok(found) => AuthorityStatus::Found {
+                            name: found.identity.name.clone(),
+                            revoked: found.identity.flags & IDENTITY_FLAG_REVOKED != 0,
+                            controlled: found.canspendfor || found.cansignfor,
+                        },
+                        Err(_) => AuthorityStatus::NotFound,
+                    },
+                    Err(RpcError::IdentityNotFound(_)) | Err(RpcError::InvalidAddress(_)) => AuthorityStatus::NotFound,
+                    Err(e) => return Err(e),
+                };
+                authorities.insert(authority.clone(), status);
+            }
+        }
+
+        let mut summary = AuditSummary::default();
+        let audits: Vec<IdentityAudit> = identities.iter()
+            .map(|identity| audit_identity(identity, &authorities, height))
+            .collect();
+
+        for audit in &audits {
+            match audit.severity {
+                Some(AuditSeverity::Critical) => summary.critical += 1,
+                Some(AuditSeverity::Warning) => summary.warning += 1,
+                Some(AuditSeverity::Info) => summary.info += 1,
+                None => summary.healthy += 1,
+            }
+        }
+
+        Ok(AuthorityAuditReport {
+            height,
+            summary,
+            identities: audits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SELF_ID: &str = "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq";
+    const OTHER_ID: &str = "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV";
+
+    fn wallet_identity(revocation: &str, recovery: &str, flags: u32, timelock: u64) -> Identity {
+        serde_json::from_value(json!({
+            "identity": {
+                "version": 3,
+                "flags": flags,
+                "primaryaddresses": ["RAddr"],
+                "minimumsignatures": 1,
+                "name": "alice",
+                "identityaddress": SELF_ID,
+                "parent": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+                "systemid": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+                "revocationauthority": revocation,
+                "recoveryauthority": recovery,
+                "timelock": timelock
+            },
+            "blockheight": 100,
+            "txid": "00",
+            "status": "active",
+            "canspendfor": true,
+            "cansignfor": true
+        }))
+        .unwrap()
+    }
+
+    fn codes(audit: &IdentityAudit) -> Vec<AuditCode> {
+        audit.findings.iter().map(|f| f.code).collect()
+    }
+
+    #[test]
+    fn test_self_authorities_are_critical() {
+        let audit = audit_identity(&wallet_identity(SELF_ID, SELF_ID, 0, 0), &HashMap::new(), 1000);
+        assert_eq!(codes(&audit), vec![AuditCode::SelfRevocationAndRecovery]);
+        assert_eq!(audit.severity, Some(AuditSeverity::Critical));
+    }
+
+    #[test]
+    fn test_external_authority_states() {
+        let authorities = HashMap::from([(
+            OTHER_ID.to_string(),
+            AuthorityStatus::Found { name: "bob".to_string(), revoked: true, controlled: false },
+        )]);
+
+        let audit = audit_identity(&wallet_identity(OTHER_ID, SELF_ID, 0, 500), &authorities, 1000);
+        assert_eq!(
+            codes(&audit),
+            vec![
+                AuditCode::AuthorityRevoked,
+                AuditCode::SelfRecovery,
+                AuditCode::TimelockExpired,
+                AuditCode::AuthorityNotControlled,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_healthy_identity() {
+        let authorities = HashMap::from([(
+            OTHER_ID.to_string(),
+            AuthorityStatus::Found { name: "bob".to_string(), revoked: false, controlled: true },
+        )]);
+
+        let audit = audit_identity(&wallet_identity(OTHER_ID, OTHER_ID, 0, 0), &authorities, 1000);
+        assert!(audit.findings.is_empty());
+        assert_eq!(audit.severity, None);
+    }
+}
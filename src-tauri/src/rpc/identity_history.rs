@@ -0,0 +1,279 @@
+// Identity Content History Timeline
+use crate::rpc::{VerusRpcClient, RpcError, IdentityDetails, IdentityHistoryEntry};
+use serde::{Deserialize, Serialize};
+
+/// Identity flag bits (matches CIdentity in the daemon)
+pub const IDENTITY_FLAG_ACTIVECURRENCY: u32 = 0x01;
+pub const IDENTITY_FLAG_LOCKED: u32 = 0x02;
+pub const IDENTITY_FLAG_TOKENIZED_CONTROL: u32 = 0x04;
+pub const IDENTITY_FLAG_REVOKED: u32 = 0x8000;
+
+const IDENTITY_FLAG_NAMES: [(u32, &str); 4] = [
+    (IDENTITY_FLAG_ACTIVECURRENCY, "activecurrency"),
+    (IDENTITY_FLAG_LOCKED, "locked"),
+    (IDENTITY_FLAG_TOKENIZED_CONTROL, "tokenizedcontrol"),
+    (IDENTITY_FLAG_REVOKED, "revoked"),
+];
+
+/// Names of the known flags set in an identity's `flags` field
+pub fn identity_flag_names(flags: u32) -> Vec<String> {
+    IDENTITY_FLAG_NAMES.iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlagChange {
+    pub from: u32,
+    pub to: u32,
+    pub set: Vec<String>,
+    pub cleared: Vec<String>,
+}
+
+/// What changed in one revision compared to the previous one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdentityChanges {
+    /// No earlier state was found, so this revision created the identity
+    pub created: bool,
+    pub primaryaddresses_added: Vec<String>,
+    pub primaryaddresses_removed: Vec<String>,
+    pub minimumsignatures: Option<FieldChange<u32>>,
+    pub revocationauthority: Option<FieldChange<String>>,
+    pub recoveryauthority: Option<FieldChange<String>>,
+    pub privateaddress: Option<FieldChange<Option<String>>>,
+    pub timelock: Option<FieldChange<u64>>,
+    pub flags: Option<FlagChange>,
+    pub contentmultimap_added: Vec<String>,
+    pub contentmultimap_removed: Vec<String>,
+    pub contentmultimap_modified: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityRevision {
+    pub height: u64,
+    pub txid: Option<String>,
+    pub vout: Option<u32>,
+    pub blockhash: Option<String>,
+    pub identity: IdentityDetails,
+    pub changes: IdentityChanges,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityTimeline {
+    pub name: String,
+    pub heightstart: Option<u64>,
+    pub heightend: Option<u64>,
+    pub revisions: Vec<IdentityRevision>,
+}
+
+/// Diff two identity revisions
+pub fn diff_identities(previous: Option<&IdentityDetails>, current: &IdentityDetails) -> IdentityChanges {
+    let previous = match previous {
+        Some(p) => p,
+        None => {
+            return IdentityChanges {
+                created: true,
+                ..IdentityChanges::default()
+            };
+        }
+    };
+
+    let mut changes = IdentityChanges {
+        primaryaddresses_added: current.primaryaddresses.iter()
+            .filter(|a| !previous.primaryaddresses.contains(a))
+            .cloned()
+            .collect(),
+        primaryaddresses_removed: previous.primaryaddresses.iter()
+            .filter(|a| !current.primaryaddresses.contains(a))
+            .cloned()
+            .collect(),
+        minimumsignatures: field_change(&previous.minimumsignatures, &current.minimumsignatures),
+        revocationauthority: field_change(&previous.revocationauthority, &current.revocationauthority),
+        recoveryauthority: field_change(&previous.recoveryauthority, &current.recoveryauthority),
+        privateaddress: field_change(&previous.privateaddress, &current.privateaddress),
+        timelock: field_change(&previous.timelock, &current.timelock),
+        ..IdentityChanges::default()
+    };
+
+    if previous.flags != current.flags {
+        let set = current.flags & !previous.flags;
+        let cleared = previous.flags & !current.flags;
+        changes.flags = Some(FlagChange {
+            from: previous.flags,
+            to: current.flags,
+            set: identity_flag_names(set),
+            cleared: identity_flag_names(cleared),
+        });
+    }
+
+    for (key, value) in &current.contentmultimap {
+        match previous.contentmultimap.get(key) {
+            None => changes.contentmultimap_added.push(key.clone()),
+            Some(old) if old != value => changes.contentmultimap_modified.push(key.clone()),
+            Some(_) => {}
+        }
+    }
+    for key in previous.contentmultimap.keys() {
+        if !current.contentmultimap.contains_key(key) {
+            changes.contentmultimap_removed.push(key.clone());
+        }
+    }
+    changes.contentmultimap_added.sort();
+    changes.contentmultimap_removed.sort();
+    changes.contentmultimap_modified.sort();
+
+    changes
+}
+
+fn field_change<T: Clone + PartialEq>(from: &T, to: &T) -> Option<FieldChange<T>> {
+    if from == to {
+        None
+    } else {
+        Some(FieldChange { from: from.clone(), to: to.clone() })
+    }
+}
+
+/// Order history entries by height and diff each against its predecessor
+pub fn build_revisions(
+    baseline: Option<IdentityDetails>,
+    mut history: Vec<IdentityHistoryEntry>
+) -> Vec<IdentityRevision> {
+    // sort_by_key is stable, so same-block updates keep daemon order
+    history.sort_by_key(|entry| entry.height);
+
+    let mut previous = baseline;
+    let mut revisions = Vec::with_capacity(history.len());
+
+    for entry in history {
+        let changes = diff_identities(previous.as_ref(), &entry.identity);
+        previous = Some(entry.identity.clone());
+
+        revisions.push(IdentityRevision {
+            height: entry.height,
+            txid: entry.output.as_ref().map(|o| o.txid.clone()),
+            vout: entry.output.as_ref().map(|o| o.voutnum),
+            blockhash: entry.blockhash,
+            identity: entry.identity,
+            changes,
+        });
+    }
+
+    revisions
+}
+
+impl VerusRpcClient {
+    /// Walk an identity's revisions between two heights with per-revision diffs
+    ///
+    /// When `heightstart` is set, the identity as of the block before it is used as
+    /// the baseline so the first revision in range is diffed rather than marked as created.
+    pub async fn get_identity_timeline(
+        &self,
+        name: &str,
+        heightstart: Option<u64>,
+        heightend: Option<u64>,
+        chain: Option<&str>
+    ) -> Result<IdentityTimeline, RpcError> {
+        let baseline = match heightstart {
+            Some(start) if start > 0 => {
+                // The identity may not exist yet at that height
+                match self.get_identity(name, Some(start - 1), None, None, chain).await {
+                    Ok(value) => value.get("identity")
+                        .and_then(|identity| serde_json::from_value::<IdentityDetails>(identity.clone()).ok()),
+                    Err(RpcError::IdentityNotFound(_)) | Err(RpcError::InvalidAddress(_)) => None,
+                    Err(e) => return Err(e),
+                }
+            }
+            _ => None,
+        };
+
+        let history = self.get_identity_history(name, heightstart, heightend, chain).await?;
+        let revisions = build_revisions(baseline, history.history);
+
+        Ok(IdentityTimeline {
+            name: history.fullyqualifiedname.unwrap_or_else(|| name.to_string()),
+            heightstart,
+            heightend,
+            revisions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn identity(flags: u32, primary: &[&str], revocation: &str, content: serde_json::Value) -> IdentityDetails {
+        serde_json::from_value(json!({
+            "version": 3,
+            "flags": flags,
+            "primaryaddresses": primary,
+            "minimumsignatures": 1,
+            "name": "alice",
+            "identityaddress": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "parent": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "systemid": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "contentmultimap": content,
+            "revocationauthority": revocation,
+            "recoveryauthority": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "timelock": 0
+        }))
+        .unwrap()
+    }
+
+    fn entry(height: u64, identity: IdentityDetails) -> IdentityHistoryEntry {
+        IdentityHistoryEntry {
+            identity,
+            blockhash: None,
+            height,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_revisions_are_ordered_and_diffed() {
+        let self_id = "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq";
+        let other_id = "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV";
+
+        let history = vec![
+            entry(300, identity(IDENTITY_FLAG_REVOKED, &["RAddrB"], other_id, json!({ "iKey1": ["aa"], "iKey2": ["cc"] }))),
+            entry(100, identity(0, &["RAddrA"], self_id, json!({}))),
+            entry(200, identity(0, &["RAddrB"], other_id, json!({ "iKey1": ["aa"] }))),
+        ];
+
+        let revisions = build_revisions(None, history);
+        let heights: Vec<u64> = revisions.iter().map(|r| r.height).collect();
+        assert_eq!(heights, vec![100, 200, 300]);
+
+        assert!(revisions[0].changes.created);
+
+        let second = &revisions[1].changes;
+        assert_eq!(second.primaryaddresses_added, vec!["RAddrB"]);
+        assert_eq!(second.primaryaddresses_removed, vec!["RAddrA"]);
+        assert_eq!(
+            second.revocationauthority,
+            Some(FieldChange { from: self_id.to_string(), to: other_id.to_string() })
+        );
+        assert_eq!(second.contentmultimap_added, vec!["iKey1"]);
+
+        let third = &revisions[2].changes;
+        assert_eq!(third.flags.as_ref().unwrap().set, vec!["revoked"]);
+        assert_eq!(third.contentmultimap_added, vec!["iKey2"]);
+        assert!(third.revocationauthority.is_none());
+    }
+
+    #[test]
+    fn test_baseline_suppresses_created() {
+        let base = identity(0, &["RAddrA"], "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq", json!({}));
+        let revisions = build_revisions(Some(base.clone()), vec![entry(500, base)]);
+        assert!(!revisions[0].changes.created);
+        assert_eq!(revisions[0].changes, IdentityChanges::default());
+    }
+}
@@ -0,0 +1,347 @@
+// Identity Name Validation and i-address Resolution
+use crate::rpc::{VerusRpcClient, RpcError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const INVALID_NAME_CHARS: &str = "\\/:*?\"<>|@.";
+const MAX_NAME_BYTES: usize = 64;
+
+/// Deepest parent chain followed when rebuilding a fully qualified name
+const MAX_NAMESPACE_DEPTH: usize = 8;
+
+/// A parsed `name.parent@` identity name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityName {
+    pub name: String,
+    /// Parent namespaces, innermost first (`alice.vlotto@` -> `["vlotto"]`)
+    pub namespace: Vec<String>,
+}
+
+impl IdentityName {
+    /// Canonical `name.parent@` form
+    pub fn fully_qualified(&self) -> String {
+        let mut parts = vec![self.name.as_str()];
+        parts.extend(self.namespace.iter().map(|s| s.as_str()));
+        format!("{}@", parts.join("."))
+    }
+}
+
+/// An identity given either by name or by i-address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum IdentityRef {
+    Address(String),
+    Name(IdentityName),
+}
+
+impl IdentityRef {
+    /// Parse and validate user input without touching the daemon
+    pub fn parse(input: &str) -> Result<Self, RpcError> {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return Err(RpcError::InvalidIdentityName("Identity name cannot be empty".to_string()));
+        }
+        if is_i_address(input) {
+            return Ok(IdentityRef::Address(input.to_string()));
+        }
+        if is_transparent_address(input) {
+            return Err(RpcError::InvalidIdentityName(format!(
+                "{} is a transparent address, not an identity", input
+            )));
+        }
+
+        let body = input.strip_suffix('@').unwrap_or(input);
+        if body.contains('@') {
+            return Err(RpcError::InvalidIdentityName(format!(
+                "'{}' may only contain '@' at the end", input
+            )));
+        }
+
+        let mut components = body.split('.');
+        let name = components.next().unwrap_or_default().to_string();
+        let namespace: Vec<String> = components.map(|s| s.to_string()).collect();
+
+        for component in std::iter::once(&name).chain(namespace.iter()) {
+            validate_name_component(component)
+                .map_err(|e| RpcError::InvalidIdentityName(format!("'{}': {}", input, e)))?;
+        }
+
+        Ok(IdentityRef::Name(IdentityName { name, namespace }))
+    }
+
+    /// The string to hand to the daemon
+    pub fn to_rpc_param(&self) -> String {
+        match self {
+            IdentityRef::Address(address) => address.clone(),
+            IdentityRef::Name(name) => name.fully_qualified(),
+        }
+    }
+}
+
+/// Check a single name component against the daemon's naming rules
+pub fn validate_name_component(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    if name.trim() != name {
+        return Err("Name cannot start or end with whitespace".to_string());
+    }
+    if name.len() > MAX_NAME_BYTES {
+        return Err(format!("Name cannot be longer than {} bytes", MAX_NAME_BYTES));
+    }
+    if let Some(c) = name.chars().find(|c| INVALID_NAME_CHARS.contains(*c) || c.is_control()) {
+        return Err(format!("Name cannot contain '{}'", c));
+    }
+    Ok(())
+}
+
+/// Check whether a string is an identity i-address
+pub fn is_i_address(value: &str) -> bool {
+    value.len() == 34
+        && value.starts_with('i')
+        && value.chars().all(|c| BASE58_ALPHABET.contains(c))
+}
+
+pub(crate) fn is_transparent_address(value: &str) -> bool {
+    value.len() == 34
+        && value.starts_with('R')
+        && value.chars().all(|c| BASE58_ALPHABET.contains(c))
+}
+
+/// Fields of an identity definition that refer to another identity
+const IDENTITY_REFERENCE_FIELDS: [&str; 3] = ["parent", "revocationauthority", "recoveryauthority"];
+
+/// Validate the names in an identity definition for `updateidentity`, `recoveridentity`
+/// or `registeridentity`, and canonicalize the fields that refer to other identities
+pub fn normalize_identity_definition(identity: &Value) -> Result<Value, RpcError> {
+    let mut identity = identity.clone();
+    let fields = identity.as_object_mut()
+        .ok_or_else(|| RpcError::ValidationError("Identity definition must be a JSON object".to_string()))?;
+
+    let name = fields.get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::InvalidIdentityName("Identity definition has no name".to_string()))?;
+    IdentityRef::parse(name)?;
+
+    for key in IDENTITY_REFERENCE_FIELDS {
+        if let Some(value) = fields.get_mut(key) {
+            if let Some(reference) = value.as_str().filter(|s| !s.is_empty()) {
+                *value = Value::String(IdentityRef::parse(reference)?.to_rpc_param());
+            }
+        }
+    }
+
+    Ok(identity)
+}
+
+/// Friendly name <-> i-address cache, owned by the client and keyed by the chain queried
+///
+/// i-addresses are derived from the name and parent, so entries never go stale.
+#[derive(Debug, Default)]
+pub struct NameCache {
+    inner: RwLock<HashMap<String, NameCacheEntries>>,
+}
+
+#[derive(Debug, Default)]
+struct NameCacheEntries {
+    by_name: HashMap<String, String>,
+    by_address: HashMap<String, String>,
+}
+
+/// Cache key for a `chain` argument; `None` is the chain the client is connected to
+fn chain_key(chain: Option<&str>) -> String {
+    chain.unwrap_or_default().to_lowercase()
+}
+
+impl NameCache {
+    fn address_for(&self, chain: Option<&str>, fully_qualified: &str) -> Option<String> {
+        self.inner.read().ok()?.get(&chain_key(chain))?.by_name.get(&fully_qualified.to_lowercase()).cloned()
+    }
+
+    fn name_for(&self, chain: Option<&str>, address: &str) -> Option<String> {
+        self.inner.read().ok()?.get(&chain_key(chain))?.by_address.get(address).cloned()
+    }
+
+    fn insert(&self, chain: Option<&str>, fully_qualified: &str, address: &str) {
+        if let Ok(mut chains) = self.inner.write() {
+            let entries = chains.entry(chain_key(chain)).or_default();
+            entries.by_name.insert(fully_qualified.to_lowercase(), address.to_string());
+            entries.by_address.insert(address.to_string(), fully_qualified.to_string());
+        }
+    }
+}
+
+/// Both forms of a resolved identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedIdentity {
+    pub identityaddress: String,
+    pub fullyqualifiedname: String,
+}
+
+impl VerusRpcClient {
+    /// Resolve a name or i-address to both its i-address and fully qualified name
+    pub async fn resolve_identity(
+        &self,
+        name_or_address: &str,
+        chain: Option<&str>
+    ) -> Result<ResolvedIdentity, RpcError> {
+        match IdentityRef::parse(name_or_address)? {
+            IdentityRef::Address(address) => {
+                let fullyqualifiedname = self.identity_name_for(&address, chain).await?;
+                Ok(ResolvedIdentity { identityaddress: address, fullyqualifiedname })
+            }
+            IdentityRef::Name(name) => {
+                let fullyqualifiedname = name.fully_qualified();
+                let identityaddress = self.identity_address_for(&fullyqualifiedname, chain).await?;
+                Ok(ResolvedIdentity { identityaddress, fullyqualifiedname })
+            }
+        }
+    }
+
+    /// i-address for a fully qualified name, from cache when possible
+    async fn identity_address_for(&self, fully_qualified: &str, chain: Option<&str>) -> Result<String, RpcError> {
+        if let Some(address) = self.name_cache.address_for(chain, fully_qualified) {
+            return Ok(address);
+        }
+
+        let identity = self.get_identity(fully_qualified, None, None, None, chain).await?;
+        let address = identity.get("identity")
+            .and_then(|i| i.get("identityaddress"))
+            .and_then(|a| a.as_str())
+            .ok_or_else(|| RpcError::IdentityNotFound(fully_qualified.to_string()))?
+            .to_string();
+
+        self.name_cache.insert(chain, fully_qualified, &address);
+        Ok(address)
+    }
+
+    /// Fully qualified name for an i-address
+    ///
+    /// Uses the `fullyqualifiedname` the daemon reports, and otherwise walks up parents
+    /// until the system namespace. Only a complete name is cached.
+    async fn identity_name_for(&self, address: &str, chain: Option<&str>) -> Result<String, RpcError> {
+        if let Some(name) = self.name_cache.name_for(chain, address) {
+            return Ok(name);
+        }
+
+        let mut components = Vec::new();
+        let mut current = address.to_string();
+        let mut complete = false;
+
+        for _ in 0..MAX_NAMESPACE_DEPTH {
+            if let Some(known) = self.name_cache.name_for(chain, &current) {
+                components.push(known.trim_end_matches('@').to_string());
+                complete = true;
+                break;
+            }
+
+            let identity = self.get_identity(&current, None, None, None, chain).await?;
+            if let Some(reported) = identity.get("fullyqualifiedname").and_then(|v| v.as_str()).filter(|n| !n.is_empty()) {
+                components.push(reported.trim_end_matches('@').to_string());
+                complete = true;
+                break;
+            }
+
+            let details = identity.get("identity")
+                .ok_or_else(|| RpcError::IdentityNotFound(current.clone()))?;
+            let field = |key: &str| details.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+            components.push(field("name"));
+
+            let parent = field("parent");
+            if parent.is_empty() || parent == field("systemid") {
+                complete = true;
+                break;
+            }
+            current = parent;
+        }
+
+        if !complete {
+            return Err(RpcError::InvalidIdentityName(format!(
+                "{} is nested more than {} namespaces deep", address, MAX_NAMESPACE_DEPTH
+            )));
+        }
+
+        let fully_qualified = format!("{}@", components.join("."));
+        self.name_cache.insert(chain, &fully_qualified, address);
+        Ok(fully_qualified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!(
+            IdentityRef::parse("alice.vlotto@").unwrap(),
+            IdentityRef::Name(IdentityName { name: "alice".to_string(), namespace: vec!["vlotto".to_string()] })
+        );
+        assert_eq!(IdentityRef::parse("alice").unwrap().to_rpc_param(), "alice@");
+        assert_eq!(
+            IdentityRef::parse("iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq").unwrap(),
+            IdentityRef::Address("iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_names() {
+        for input in ["", "@", "alice..vlotto@", "al@ice", "a/b@", "RMzd5vMptsxxz1tWH2FeSdUgRSNgS4G52w"] {
+            assert!(
+                matches!(IdentityRef::parse(input), Err(RpcError::InvalidIdentityName(_))),
+                "expected {:?} to be rejected", input
+            );
+        }
+        assert!(IdentityRef::parse(&format!("{}@", "a".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn test_normalize_identity_definition() {
+        let normalized = normalize_identity_definition(&serde_json::json!({
+            "name": "alice",
+            "parent": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "revocationauthority": "bob.vlotto",
+            "recoveryauthority": "",
+            "primaryaddresses": ["RMzd5vMptsxxz1tWH2FeSdUgRSNgS4G52w"]
+        }))
+        .unwrap();
+        assert_eq!(normalized["name"], "alice");
+        assert_eq!(normalized["parent"], "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq");
+        assert_eq!(normalized["revocationauthority"], "bob.vlotto@");
+        assert_eq!(normalized["recoveryauthority"], "");
+
+        assert!(normalize_identity_definition(&serde_json::json!({ "name": "al/ice" })).is_err());
+        assert!(normalize_identity_definition(&serde_json::json!({ "name": "alice", "parent": "a..b" })).is_err());
+        assert!(normalize_identity_definition(&serde_json::json!({ "parent": "vlotto" })).is_err());
+    }
+
+    #[test]
+    fn test_name_cache_is_case_insensitive() {
+        let cache = NameCache::default();
+        cache.insert(None, "Alice.vlotto@", "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq");
+        assert_eq!(cache.address_for(None, "alice.VLOTTO@").as_deref(), Some("iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq"));
+        assert_eq!(cache.name_for(None, "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq").as_deref(), Some("Alice.vlotto@"));
+        assert!(cache.address_for(None, "bob.vlotto@").is_none());
+    }
+
+    #[test]
+    fn test_name_cache_is_per_chain() {
+        let cache = NameCache::default();
+        cache.insert(Some("vrsctest"), "alice@", "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq");
+        assert_eq!(cache.address_for(Some("VRSCTEST"), "alice@").as_deref(), Some("iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq"));
+        assert!(cache.address_for(None, "alice@").is_none());
+        assert!(cache.name_for(Some("vdex"), "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq").is_none());
+    }
+
+    #[test]
+    fn test_is_i_address() {
+        assert!(is_i_address("i4GC1YGEVD21afWudGoFJVdnfjJ5XWnCQv"));
+        assert!(!is_i_address("vlotto.ledger.data"));
+        assert!(!is_i_address("RMzd5vMptsxxz1tWH2FeSdUgRSNgS4G52w"));
+    }
+}
@@ -0,0 +1,369 @@
+// Currency Launch Lifecycle Tracker
+use crate::rpc::{VerusRpcClient, RpcError, Identity};
+use crate::rpc::amount::Amount;
+use crate::rpc::currency_definition::{CurrencyDefinition, CurrencyState};
+use crate::rpc::identity_history::IDENTITY_FLAG_ACTIVECURRENCY;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Currency state flag bits (matches CCoinbaseCurrencyState in the daemon)
+pub const CURRENCY_STATE_FLAG_REFUNDING: u32 = 0x02;
+pub const CURRENCY_STATE_FLAG_PRELAUNCH: u32 = 0x04;
+pub const CURRENCY_STATE_FLAG_LAUNCHCONFIRMED: u32 = 0x10;
+
+/// Event emitted when a tracked launch changes phase, prediction or preconversion totals
+pub const LAUNCH_EVENT: &str = "currency-launch-changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchPhase {
+    /// The definition has not been mined yet
+    Pending,
+    /// Before `startblock`, accepting preconversions
+    Preconversion,
+    /// Before `startblock`, without reserves to preconvert from
+    Prelaunch,
+    /// `startblock` has passed but the launch is not confirmed yet
+    AwaitingLaunch,
+    Launched,
+    Refunding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchPrediction {
+    Launch,
+    Refund,
+}
+
+/// Preconversions into one reserve compared against its limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreconversionProgress {
+    pub currencyid: String,
+    pub name: Option<String>,
+    pub preconverted: Amount,
+    pub minimum: Option<Amount>,
+    pub maximum: Option<Amount>,
+    pub minimum_met: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchStatus {
+    pub currency: String,
+    pub currencyid: Option<String>,
+    pub phase: LaunchPhase,
+    pub height: u64,
+    pub startblock: Option<u64>,
+    pub blocks_remaining: Option<u64>,
+    pub preconversions: Vec<PreconversionProgress>,
+    /// Expected outcome while the launch is still undecided
+    pub prediction: Option<LaunchPrediction>,
+    /// Why the currency could not be looked up on the last poll
+    pub error: Option<String>,
+}
+
+impl LaunchStatus {
+    fn pending(currency: &str, height: u64) -> Self {
+        Self {
+            currency: currency.to_string(),
+            currencyid: None,
+            phase: LaunchPhase::Pending,
+            height,
+            startblock: None,
+            blocks_remaining: None,
+            preconversions: Vec::new(),
+            prediction: None,
+            error: None,
+        }
+    }
+
+    /// Whether two statuses differ in anything other than the passing of blocks
+    fn differs_from(&self, other: &LaunchStatus) -> bool {
+        self.phase != other.phase
+            || self.prediction != other.prediction
+            || self.preconversions != other.preconversions
+            || self.error != other.error
+    }
+}
+
+/// Work out where a currency is in its launch from its definition and latest state
+pub fn launch_status(
+    currency: &str,
+    definition: &CurrencyDefinition,
+    state: Option<&CurrencyState>,
+    height: u64
+) -> LaunchStatus {
+    let flags = state.map(|s| s.flags).unwrap_or(0);
+    let startblock = definition.startblock.filter(|s| *s > 0);
+    let before_start = startblock.is_some_and(|start| height < start);
+
+    let preconversions: Vec<PreconversionProgress> = definition.currencies.iter()
+        .enumerate()
+        .map(|(index, currencyid)| {
+            let preconverted = state.map(|s| preconverted_amount(s, currencyid)).unwrap_or(Amount::ZERO);
+            let minimum = definition.minpreconversion.get(index).copied().filter(|m| m.is_positive());
+            PreconversionProgress {
+                currencyid: currencyid.clone(),
+                name: definition.currencynames.get(currencyid).cloned(),
+                preconverted,
+                minimum,
+                maximum: definition.maxpreconversion.get(index).copied(),
+                minimum_met: minimum.is_none_or(|m| preconverted >= m),
+            }
+        })
+        .collect();
+
+    let phase = if flags & CURRENCY_STATE_FLAG_REFUNDING != 0 {
+        LaunchPhase::Refunding
+    } else if flags & CURRENCY_STATE_FLAG_LAUNCHCONFIRMED != 0 {
+        LaunchPhase::Launched
+    } else if before_start || (flags & CURRENCY_STATE_FLAG_PRELAUNCH != 0 && startblock.is_none()) {
+        if definition.currencies.is_empty() {
+            LaunchPhase::Prelaunch
+        } else {
+            LaunchPhase::Preconversion
+        }
+    } else {
+        LaunchPhase::AwaitingLaunch
+    };
+
+    let prediction = match phase {
+        LaunchPhase::Preconversion | LaunchPhase::Prelaunch | LaunchPhase::AwaitingLaunch => {
+            if preconversions.iter().all(|p| p.minimum_met) {
+                Some(LaunchPrediction::Launch)
+            } else {
+                Some(LaunchPrediction::Refund)
+            }
+        }
+        _ => None,
+    };
+
+    LaunchStatus {
+        currency: currency.to_string(),
+        currencyid: Some(definition.currencyid.clone()),
+        phase,
+        height,
+        startblock,
+        blocks_remaining: startblock.map(|start| start.saturating_sub(height)),
+        preconversions,
+        prediction,
+        error: None,
+    }
+}
+
+/// Amount preconverted from a reserve, from the basket reserves or the prelaunch totals
+fn preconverted_amount(state: &CurrencyState, currencyid: &str) -> Amount {
+    state.reservecurrencies.iter()
+        .find(|r| r.currencyid == currencyid)
+        .map(|r| r.reserves)
+        .or_else(|| state.currencies.get(currencyid).map(|c| c.reservein))
+        .unwrap_or(Amount::ZERO)
+}
+
+/// Currencies being followed and the last status reported for each
+#[derive(Debug, Default)]
+pub struct LaunchTracker {
+    tracked: RwLock<HashSet<String>>,
+    last: RwLock<HashMap<String, LaunchStatus>>,
+    running: AtomicBool,
+    /// Bumped on every start and stop so a poller from an earlier start knows to exit
+    generation: AtomicU64,
+}
+
+impl LaunchTracker {
+    /// Follow a currency that is not yet on chain, e.g. right after `define_currency`
+    pub fn track(&self, currency: &str) {
+        if let Ok(mut tracked) = self.tracked.write() {
+            tracked.insert(currency.to_string());
+        }
+    }
+
+    pub fn untrack(&self, currency: &str) {
+        if let Ok(mut tracked) = self.tracked.write() {
+            tracked.remove(currency);
+        }
+        if let Ok(mut last) = self.last.write() {
+            last.remove(currency);
+        }
+    }
+
+    fn tracked(&self) -> Vec<String> {
+        self.tracked.read().map(|t| t.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Store a status, returning whether it changed since the last one
+    pub fn record(&self, status: &LaunchStatus) -> bool {
+        let Ok(mut last) = self.last.write() else {
+            return false;
+        };
+        let changed = last.get(&status.currency).is_none_or(|previous| status.differs_from(previous));
+        last.insert(status.currency.clone(), status.clone());
+        changed
+    }
+
+    /// Mark the background poller as running, returning its generation or `None` if it already was
+    pub fn start(&self) -> Option<u64> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        Some(self.generation.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Whether the poller started as `generation` should keep going
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.running.load(Ordering::SeqCst) && self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+impl VerusRpcClient {
+    /// Launch status of one currency at the current height
+    pub async fn get_launch_status(&self, currency: &str, height: u64) -> Result<LaunchStatus, RpcError> {
+        let definition = match self.get_currency(currency, None).await {
+            Ok(definition) => definition,
+            Err(RpcError::CurrencyNotFound(_)) | Err(RpcError::InvalidAddress(_)) => {
+                return Ok(LaunchStatus::pending(currency, height));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let state = match &definition.bestcurrencystate {
+            Some(state) => Some(state.clone()),
+            None => {
+                let states = self.get_currency_state(currency, None, None).await?;
+                states.get(0)
+                    .and_then(|s| s.get("currencystate"))
+                    .and_then(|s| serde_json::from_value::<CurrencyState>(s.clone()).ok())
+            }
+        };
+
+        Ok(launch_status(currency, &definition, state.as_ref(), height))
+    }
+
+    /// Launch status of every currency defined on a wallet identity or explicitly tracked
+    pub async fn get_launch_statuses(&self, tracker: &LaunchTracker) -> Result<Vec<LaunchStatus>, RpcError> {
+        let height = self.get_block_count(None).await?;
+        let identities: Vec<Identity> = serde_json::from_value(
+            self.list_identities(None, None, None, None).await?
+        )?;
+
+        let mut currencies: Vec<String> = identities.iter()
+            .filter(|i| i.identity.flags & IDENTITY_FLAG_ACTIVECURRENCY != 0)
+            .map(|i| i.identity.identityaddress.clone())
+            .collect();
+        for tracked in tracker.tracked() {
+            if !currencies.contains(&tracked) {
+                currencies.push(tracked);
+            }
+        }
+
+        // A name tracked before its definition was mined shows up again under its i-address
+        let mut seen = HashSet::new();
+        let mut statuses = Vec::with_capacity(currencies.len());
+        for currency in currencies {
+            // One currency failing to look up should not hide the others
+            let status = match self.get_launch_status(&currency, height).await {
+                Ok(status) => status,
+                Err(e) => LaunchStatus { error: Some(e.to_string()), ..LaunchStatus::pending(&currency, height) },
+            };
+            if status.currencyid.as_ref().is_none_or(|id| seen.insert(id.clone())) {
+                statuses.push(status);
+            }
+        }
+        Ok(statuses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::VRSC;
+    use serde_json::json;
+
+    fn basket(startblock: u64) -> CurrencyDefinition {
+        serde_json::from_value(json!({
+            "name": "basket",
+            "currencyid": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "options": 33,
+            "startblock": startblock,
+            "currencies": [VRSC],
+            "weights": [1.0],
+            "minpreconversion": [100.0],
+            "maxpreconversion": [1000.0],
+            "currencynames": { VRSC: "VRSC" }
+        }))
+        .unwrap()
+    }
+
+    fn state(flags: u32, preconverted: f64) -> CurrencyState {
+        serde_json::from_value(json!({
+            "flags": flags,
+            "reservecurrencies": [{ "currencyid": VRSC, "weight": 1.0, "reserves": preconverted, "priceinreserve": 1.0 }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_preconversion_window_predicts_outcome() {
+        let status = launch_status("basket", &basket(1100), Some(&state(CURRENCY_STATE_FLAG_PRELAUNCH | 1, 40.0)), 1000);
+        assert_eq!(status.phase, LaunchPhase::Preconversion);
+        assert_eq!(status.blocks_remaining, Some(100));
+        assert_eq!(status.prediction, Some(LaunchPrediction::Refund));
+        assert_eq!(status.preconversions[0].name.as_deref(), Some("VRSC"));
+
+        let funded = launch_status("basket", &basket(1100), Some(&state(CURRENCY_STATE_FLAG_PRELAUNCH | 1, 150.0)), 1000);
+        assert_eq!(funded.prediction, Some(LaunchPrediction::Launch));
+
+        // A total the daemon reports as 99.99999999999999 is exactly the minimum to the satoshi
+        let exact = launch_status("basket", &basket(1100), Some(&state(CURRENCY_STATE_FLAG_PRELAUNCH | 1, 99.99999999999999)), 1000);
+        assert_eq!(exact.preconversions[0].preconverted, exact.preconversions[0].minimum.unwrap());
+        assert_eq!(exact.prediction, Some(LaunchPrediction::Launch));
+    }
+
+    #[test]
+    fn test_launch_outcomes() {
+        let launched = launch_status("basket", &basket(1100), Some(&state(CURRENCY_STATE_FLAG_LAUNCHCONFIRMED | 1, 150.0)), 1200);
+        assert_eq!(launched.phase, LaunchPhase::Launched);
+        assert_eq!(launched.blocks_remaining, Some(0));
+        assert_eq!(launched.prediction, None);
+
+        let refunding = launch_status("basket", &basket(1100), Some(&state(CURRENCY_STATE_FLAG_REFUNDING | 1, 40.0)), 1200);
+        assert_eq!(refunding.phase, LaunchPhase::Refunding);
+
+        let waiting = launch_status("basket", &basket(1100), Some(&state(1, 150.0)), 1100);
+        assert_eq!(waiting.phase, LaunchPhase::AwaitingLaunch);
+    }
+
+    #[test]
+    fn test_tracker_reports_only_changes() {
+        let tracker = LaunchTracker::default();
+        let mut status = launch_status("basket", &basket(1100), Some(&state(1, 40.0)), 1000);
+        assert!(tracker.record(&status));
+
+        status.height = 1001;
+        status.blocks_remaining = Some(99);
+        assert!(!tracker.record(&status));
+
+        status.preconversions[0].preconverted = "120".parse().unwrap();
+        assert!(tracker.record(&status));
+    }
+
+    #[test]
+    fn test_restart_retires_previous_poller() {
+        let tracker = LaunchTracker::default();
+        let first = tracker.start().unwrap();
+        assert_eq!(tracker.start(), None);
+        assert!(tracker.is_current(first));
+
+        tracker.stop();
+        let second = tracker.start().unwrap();
+        assert!(!tracker.is_current(first));
+        assert!(tracker.is_current(second));
+    }
+}
@@ -4,6 +4,33 @@ pub mod error;
 pub mod commands;
 pub mod chain_discovery;
 pub mod credential_manager;
+pub mod vdxf;
+pub mod identity_history;
+pub mod identity_audit;
+pub mod subid_registration;
+pub mod identity_name;
+pub mod currency_definition;
+pub mod currency_options;
+pub mod currency_validation;
+pub mod launch_tracker;
+pub mod currency_series;
+pub mod chain_cache;
+pub mod conversion_routes;
+pub mod protected_conversion;
+pub mod send_builder;
+pub mod amount;
+pub mod offers;
+pub mod offer_monitor;
+pub mod order_book;
+pub mod offer_builder;
+pub mod offer_verification;
+pub mod wallet_history;
+pub mod tx_inspector;
+pub mod history_export;
+pub mod fee_estimator;
+pub mod transfer_tracker;
+#[cfg(test)]
+pub(crate) mod test_util;
 
 pub use client::VerusRpcClient;
 pub use types::*;
@@ -0,0 +1,439 @@
+// Typed makeoffer / takeoffer Requests
+use crate::rpc::{VerusRpcClient, RpcError, MakeOfferResponse, TakeOfferResponse};
+use crate::rpc::amount::Amount;
+use crate::rpc::currency_validation::{field_errors_result, FieldError};
+use crate::rpc::identity_name::{is_i_address, is_transparent_address, validate_name_component, IdentityRef};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// What the maker puts up, or what the taker delivers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OfferGive {
+    Currency { currency: String, amount: Amount },
+    Identity { identity: String },
+}
+
+impl OfferGive {
+    /// `offer` object of `makeoffer`
+    pub fn to_offer_param(&self) -> Value {
+        match self {
+            OfferGive::Currency { currency, amount } => json!({ "currency": currency, "amount": amount }),
+            OfferGive::Identity { identity } => json!({ "identity": identity }),
+        }
+    }
+
+    /// `deliver` value of `takeoffer`, which takes an identity as a bare name
+    pub fn to_deliver_param(&self) -> Value {
+        match self {
+            OfferGive::Currency { .. } => self.to_offer_param(),
+            OfferGive::Identity { identity } => json!(identity),
+        }
+    }
+}
+
+/// Identity as it should look once it changes hands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferIdentityDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    pub primaryaddresses: Vec<String>,
+    pub minimumsignatures: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocationauthority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recoveryauthority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privateaddress: Option<String>,
+}
+
+/// What the maker asks for, or what the taker accepts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OfferReceive {
+    Currency { address: String, currency: String, amount: Amount },
+    Identity(OfferIdentityDefinition),
+}
+
+impl OfferReceive {
+    /// `for` object of `makeoffer`, or `accept` of `takeoffer`
+    pub fn to_param(&self) -> Value {
+        match self {
+            OfferReceive::Currency { address, currency, amount } => {
+                json!({ "address": address, "currency": currency, "amount": amount })
+            }
+            OfferReceive::Identity(definition) => serde_json::to_value(definition).unwrap_or(Value::Null),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MakeOfferRequest {
+    pub from_address: String,
+    pub offer: OfferGive,
+    #[serde(rename = "for")]
+    pub receive: OfferReceive,
+    /// Defaults to `from_address`
+    #[serde(default)]
+    pub changeaddress: Option<String>,
+    /// Defaults to the daemon's expiry, 20 blocks after the offer is mined
+    #[serde(default)]
+    pub expiryheight: Option<u64>,
+}
+
+impl MakeOfferRequest {
+    fn change_address(&self) -> &str {
+        self.changeaddress.as_deref().unwrap_or(&self.from_address)
+    }
+
+    /// The `makeoffer` argument the frontend used to assemble by hand
+    pub fn to_offer_data(&self) -> Value {
+        let mut data = json!({
+            "changeaddress": self.change_address(),
+            "offer": self.offer.to_offer_param(),
+            "for": self.receive.to_param(),
+        });
+        if let Some(height) = self.expiryheight {
+            data["expiryheight"] = json!(height);
+        }
+        data
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TakeOfferRequest {
+    pub from_address: String,
+    pub txid: String,
+    pub deliver: OfferGive,
+    pub accept: OfferReceive,
+    /// Defaults to `from_address`
+    #[serde(default)]
+    pub changeaddress: Option<String>,
+}
+
+impl TakeOfferRequest {
+    fn change_address(&self) -> &str {
+        self.changeaddress.as_deref().unwrap_or(&self.from_address)
+    }
+
+    /// The `takeoffer` argument the frontend used to assemble by hand
+    pub fn to_offer_data(&self) -> Value {
+        json!({
+            "txid": self.txid,
+            "changeaddress": self.change_address(),
+            "deliver": self.deliver.to_deliver_param(),
+            "accept": self.accept.to_param(),
+        })
+    }
+}
+
+/// Whether the wallet can give up the identity it is offering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OfferedIdentity {
+    Controlled,
+    NotControlled,
+    NotFound,
+}
+
+/// The wallet's holdings of the currency it is offering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OfferedFunds {
+    Held(Amount),
+    UnknownCurrency,
+}
+
+/// Chain state an offer is checked against
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfferContext {
+    pub height: u64,
+    pub identity: Option<OfferedIdentity>,
+    pub funds: Option<OfferedFunds>,
+}
+
+/// R-address, i-address or `name@` the daemon can pay to
+fn is_destination(value: &str) -> bool {
+    let value = value.trim();
+    is_transparent_address(value)
+        || is_i_address(value)
+        || (value.ends_with('@') && IdentityRef::parse(value).is_ok())
+}
+
+fn give_errors(give: &OfferGive, field: &str, context: &OfferContext) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    match give {
+        OfferGive::Currency { currency, amount } => {
+            if currency.trim().is_empty() {
+                errors.push(FieldError::new(format!("{}.currency", field), "Currency is required"));
+            }
+            if !amount.is_positive() {
+                errors.push(FieldError::new(format!("{}.amount", field), "Amount must be greater than zero"));
+            }
+            match context.funds {
+                Some(OfferedFunds::Held(held)) if held < *amount => errors.push(FieldError::new(
+                    format!("{}.amount", field),
+                    format!("Only {} {} is available to offer", held, currency),
+                )),
+                Some(OfferedFunds::UnknownCurrency) => errors.push(FieldError::new(
+                    format!("{}.currency", field),
+                    format!("Currency {} does not exist", currency),
+                )),
+                _ => {}
+            }
+        }
+        OfferGive::Identity { identity } => {
+            if let Err(e) = IdentityRef::parse(identity) {
+                errors.push(FieldError::new(format!("{}.identity", field), e.to_string()));
+            }
+            match context.identity {
+                Some(OfferedIdentity::NotControlled) => errors.push(FieldError::new(
+                    format!("{}.identity", field),
+                    format!("Identity {} is not controlled by this wallet", identity),
+                )),
+                Some(OfferedIdentity::NotFound) => errors.push(FieldError::new(
+                    format!("{}.identity", field),
+                    format!("Identity {} does not exist", identity),
+                )),
+                _ => {}
+            }
+        }
+    }
+    errors
+}
+
+fn receive_errors(receive: &OfferReceive, field: &str) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let error = |name: &str, message: String| FieldError::new(format!("{}.{}", field, name), message);
+    match receive {
+        OfferReceive::Currency { address, currency, amount } => {
+            if !is_destination(address) {
+                errors.push(error("address", format!("'{}' is not a valid address or identity", address)));
+            }
+            if currency.trim().is_empty() {
+                errors.push(error("currency", "Currency is required".to_string()));
+            }
+            if !amount.is_positive() {
+                errors.push(error("amount", "Amount must be greater than zero".to_string()));
+            }
+        }
+        OfferReceive::Identity(definition) => {
+            if let Err(e) = validate_name_component(&definition.name) {
+                errors.push(error("name", e));
+            }
+            if definition.primaryaddresses.is_empty() {
+                errors.push(error("primaryaddresses", "At least one primary address is required".to_string()));
+            }
+            for (index, address) in definition.primaryaddresses.iter().enumerate() {
+                if !is_transparent_address(address) {
+                    errors.push(error(&format!("primaryaddresses[{}]", index), format!("'{}' is not an R-address", address)));
+                }
+            }
+            let signers = definition.primaryaddresses.len() as u32;
+            if definition.minimumsignatures == 0 || definition.minimumsignatures > signers.max(1) {
+                errors.push(error("minimumsignatures", format!("Must be between 1 and {}", signers.max(1))));
+            }
+            let authorities = [
+                ("revocationauthority", &definition.revocationauthority),
+                ("recoveryauthority", &definition.recoveryauthority),
+            ];
+            for (name, authority) in authorities {
+                if let Some(Err(e)) = authority.as_deref().map(IdentityRef::parse) {
+                    errors.push(error(name, e.to_string()));
+                }
+            }
+        }
+    }
+    errors
+}
+
+fn same_currency(give: &OfferGive, receive: &OfferReceive) -> bool {
+    matches!(
+        (give, receive),
+        (OfferGive::Currency { currency: a, .. }, OfferReceive::Currency { currency: b, .. }) if a.eq_ignore_ascii_case(b)
+    )
+}
+
+/// Check a `makeoffer` request against the wallet's holdings and the chain tip
+pub fn validate_make_offer(request: &MakeOfferRequest, context: &OfferContext) -> Vec<FieldError> {
+    let mut errors = give_errors(&request.offer, "offer", context);
+    errors.extend(receive_errors(&request.receive, "for"));
+    if same_currency(&request.offer, &request.receive) {
+        errors.push(FieldError::new("for.currency", "Cannot offer a currency for itself"));
+    }
+    if !is_destination(request.change_address()) {
+        errors.push(FieldError::new("changeaddress", format!("'{}' is not a valid change address", request.change_address())));
+    }
+    if let Some(height) = request.expiryheight {
+        if height <= context.height {
+            errors.push(FieldError::new(
+                "expiryheight",
+                format!("Expiry height {} is not after the current height {}", height, context.height),
+            ));
+        }
+    }
+    errors
+}
+
+/// Check a `takeoffer` request against the wallet's holdings
+pub fn validate_take_offer(request: &TakeOfferRequest, context: &OfferContext) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if request.txid.len() != 64 || !request.txid.chars().all(|c| c.is_ascii_hexdigit()) {
+        errors.push(FieldError::new("txid", "Offer txid must be 64 hex characters"));
+    }
+    errors.extend(give_errors(&request.deliver, "deliver", context));
+    errors.extend(receive_errors(&request.accept, "accept"));
+    if same_currency(&request.deliver, &request.accept) {
+        errors.push(FieldError::new("accept.currency", "Cannot exchange a currency for itself"));
+    }
+    if !is_destination(request.change_address()) {
+        errors.push(FieldError::new("changeaddress", format!("'{}' is not a valid change address", request.change_address())));
+    }
+    errors
+}
+
+impl VerusRpcClient {
+    /// Look up whether `from_address` can give up what an offer puts up
+    async fn offer_give_context(&self, from_address: &str, give: &OfferGive) -> Result<OfferContext, RpcError> {
+        let height = self.get_block_count(None).await?;
+        let mut context = OfferContext { height, identity: None, funds: None };
+
+        match give {
+            OfferGive::Identity { identity } => {
+                let Ok(parsed) = IdentityRef::parse(identity) else {
+                    return Ok(context);
+                };
+                context.identity = Some(match self.get_identity(&parsed.to_rpc_param(), None, None, None, None).await {
+                    Ok(value) => {
+                        let flag = |key: &str| value.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+                        if flag("canspendfor") || flag("cansignfor") {
+                            OfferedIdentity::Controlled
+                        } else {
+                            OfferedIdentity::NotControlled
+                        }
+                    }
+                    Err(RpcError::IdentityNotFound(_)) | Err(RpcError::InvalidAddress(_)) => OfferedIdentity::NotFound,
+                    Err(e) => return Err(e),
+                });
+            }
+            OfferGive::Currency { currency, .. } if !currency.trim().is_empty() => {
+                context.funds = Some(match self.get_currency(currency, None).await {
+                    Ok(definition) => {
+                        // Keyed by i-address without friendly names
+                        let balances = self.get_currency_balance(from_address, Some(1), Some(false), None, None).await?;
+                        let held = balances.get(&definition.currencyid)
+                            .and_then(|v| Amount::deserialize(v).ok())
+                            .unwrap_or(Amount::ZERO);
+                        OfferedFunds::Held(held)
+                    }
+                    Err(RpcError::CurrencyNotFound(_)) | Err(RpcError::InvalidAddress(_)) => OfferedFunds::UnknownCurrency,
+                    Err(e) => return Err(e),
+                });
+            }
+            OfferGive::Currency { .. } => {}
+        }
+        Ok(context)
+    }
+
+    pub async fn validate_make_offer(&self, request: &MakeOfferRequest) -> Result<Vec<FieldError>, RpcError> {
+        let context = self.offer_give_context(&request.from_address, &request.offer).await?;
+        Ok(validate_make_offer(request, &context))
+    }
+
+    pub async fn validate_take_offer(&self, request: &TakeOfferRequest) -> Result<Vec<FieldError>, RpcError> {
+        let context = self.offer_give_context(&request.from_address, &request.deliver).await?;
+        Ok(validate_take_offer(request, &context))
+    }
+
+    /// Validate and submit a typed `makeoffer`
+    pub async fn make_offer_checked(
+        &self,
+        request: &MakeOfferRequest,
+        return_tx: Option<bool>
+    ) -> Result<MakeOfferResponse, RpcError> {
+        field_errors_result(self.validate_make_offer(request).await?)?;
+        self.make_offer(&request.from_address, &request.to_offer_data(), return_tx).await
+    }
+
+    /// Validate and submit a typed `takeoffer`
+    pub async fn take_offer_checked(
+        &self,
+        request: &TakeOfferRequest,
+        return_tx: Option<bool>
+    ) -> Result<TakeOfferResponse, RpcError> {
+        field_errors_result(self.validate_take_offer(request).await?)?;
+        self.take_offer(&request.from_address, &request.to_offer_data(), return_tx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::amount;
+
+    const R_ADDRESS: &str = "RYX7dEkNRpjCQEy8jMBDtSPsRNhLAJ3UBM";
+
+    fn context(funds: Option<OfferedFunds>, identity: Option<OfferedIdentity>) -> OfferContext {
+        OfferContext { height: 1000, identity, funds }
+    }
+
+    fn identity_definition() -> OfferIdentityDefinition {
+        OfferIdentityDefinition {
+            name: "alice".to_string(),
+            parent: None,
+            primaryaddresses: vec![R_ADDRESS.to_string()],
+            minimumsignatures: 1,
+            revocationauthority: None,
+            recoveryauthority: None,
+            privateaddress: None,
+        }
+    }
+
+    fn fields(errors: Vec<FieldError>) -> Vec<String> {
+        errors.into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn test_make_offer_data() {
+        let request: MakeOfferRequest = serde_json::from_value(json!({
+            "from_address": R_ADDRESS,
+            "offer": { "type": "currency", "currency": "VRSC", "amount": 10 },
+            "for": { "type": "identity", "name": "alice", "primaryaddresses": [R_ADDRESS], "minimumsignatures": 1 },
+            "expiryheight": 1100
+        })).unwrap();
+        assert_eq!(request.to_offer_data(), json!({
+            "changeaddress": R_ADDRESS,
+            "expiryheight": 1100,
+            "offer": { "currency": "VRSC", "amount": 10.0 },
+            "for": { "name": "alice", "primaryaddresses": [R_ADDRESS], "minimumsignatures": 1 }
+        }));
+        assert!(validate_make_offer(&request, &context(Some(OfferedFunds::Held(amount("10"))), None)).is_empty());
+    }
+
+    #[test]
+    fn test_make_offer_checks_funds_expiry_and_change() {
+        let request = MakeOfferRequest {
+            from_address: R_ADDRESS.to_string(),
+            offer: OfferGive::Currency { currency: "VRSC".to_string(), amount: amount("10") },
+            receive: OfferReceive::Currency { address: "bob@".to_string(), currency: "vrsc".to_string(), amount: amount("5") },
+            changeaddress: Some("not an address".to_string()),
+            expiryheight: Some(1000),
+        };
+        let errors = validate_make_offer(&request, &context(Some(OfferedFunds::Held(amount("9.5"))), None));
+        assert_eq!(fields(errors), vec!["offer.amount", "for.currency", "changeaddress", "expiryheight"]);
+    }
+
+    #[test]
+    fn test_take_offer_checks_identity_control() {
+        let request = TakeOfferRequest {
+            from_address: R_ADDRESS.to_string(),
+            txid: "ab".repeat(32),
+            deliver: OfferGive::Identity { identity: "alice@".to_string() },
+            accept: OfferReceive::Identity(OfferIdentityDefinition { minimumsignatures: 2, ..identity_definition() }),
+            changeaddress: None,
+        };
+        assert_eq!(request.to_offer_data()["deliver"], json!("alice@"));
+
+        let errors = validate_take_offer(&request, &context(None, Some(OfferedIdentity::NotControlled)));
+        assert_eq!(fields(errors), vec!["deliver.identity", "accept.minimumsignatures"]);
+    }
+}
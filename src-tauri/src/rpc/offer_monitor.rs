@@ -0,0 +1,299 @@
+// Open Offer Expiry Monitor
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::offers::{parse_open_offers, MarketOffer};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Event emitted when a wallet offer comes within the warning window of its expiry
+pub const OFFER_EXPIRING_EVENT: &str = "offer-expiring";
+
+/// Event emitted when a wallet offer expires, with the result of reclaiming it if enabled
+pub const OFFER_EXPIRED_EVENT: &str = "offer-expired";
+
+fn default_warning_blocks() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferMonitorConfig {
+    /// Blocks before expiry at which `offer-expiring` is emitted
+    #[serde(default = "default_warning_blocks")]
+    pub warning_blocks: u64,
+    /// Close expired offers automatically, returning their funds to `destination_address`
+    #[serde(default)]
+    pub auto_close: bool,
+    #[serde(default)]
+    pub destination_address: Option<String>,
+}
+
+impl Default for OfferMonitorConfig {
+    fn default() -> Self {
+        Self {
+            warning_blocks: default_warning_blocks(),
+            auto_close: false,
+            destination_address: None,
+        }
+    }
+}
+
+impl OfferMonitorConfig {
+    pub fn validate(&self) -> Result<(), RpcError> {
+        let has_destination = self.destination_address.as_deref().is_some_and(|d| !d.trim().is_empty());
+        if self.auto_close && !has_destination {
+            return Err(RpcError::ValidationError(
+                "A destination address is required to close expired offers automatically".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OfferNotice {
+    Expiring,
+    Expired,
+}
+
+impl OfferNotice {
+    pub fn event(self) -> &'static str {
+        match self {
+            OfferNotice::Expiring => OFFER_EXPIRING_EVENT,
+            OfferNotice::Expired => OFFER_EXPIRED_EVENT,
+        }
+    }
+}
+
+/// Where the notice for an offer stands at a given height, if it needs one
+pub fn offer_notice(offer: &MarketOffer, height: u64, warning_blocks: u64) -> Option<OfferNotice> {
+    if offer.expired || offer.blockexpiry <= height {
+        Some(OfferNotice::Expired)
+    } else if offer.blockexpiry - height <= warning_blocks {
+        Some(OfferNotice::Expiring)
+    } else {
+        None
+    }
+}
+
+/// Outcome of closing an expired offer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferReclaim {
+    pub destination: String,
+    pub closed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferExpiryEvent {
+    pub notice: OfferNotice,
+    pub offer: MarketOffer,
+    pub height: u64,
+    pub blocks_remaining: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reclaim: Option<OfferReclaim>,
+}
+
+/// Wallet offers already reported, and the settings the background poller uses
+#[derive(Debug, Default)]
+pub struct OfferMonitor {
+    config: RwLock<OfferMonitorConfig>,
+    notified: RwLock<HashMap<String, OfferNotice>>,
+    /// Expired offers a close has been attempted for, so a failing close is not retried every poll
+    reclaimed: RwLock<HashSet<String>>,
+    running: AtomicBool,
+    /// Bumped on every start and stop so a poller from an earlier start knows to exit
+    generation: AtomicU64,
+}
+
+impl OfferMonitor {
+    pub fn configure(&self, config: OfferMonitorConfig) -> Result<(), RpcError> {
+        config.validate()?;
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+        Ok(())
+    }
+
+    pub fn config(&self) -> OfferMonitorConfig {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Events for offers whose notice changed since the last review
+    ///
+    /// Offers missing from `offers` were closed or taken, and are forgotten.
+    pub fn review(&self, offers: &[MarketOffer], height: u64) -> Vec<OfferExpiryEvent> {
+        let warning_blocks = self.config().warning_blocks;
+        let Ok(mut notified) = self.notified.write() else {
+            return Vec::new();
+        };
+        notified.retain(|txid, _| offers.iter().any(|o| &o.txid == txid));
+        if let Ok(mut reclaimed) = self.reclaimed.write() {
+            reclaimed.retain(|txid| offers.iter().any(|o| &o.txid == txid));
+        }
+
+        offers.iter()
+            .filter_map(|offer| {
+                let notice = offer_notice(offer, height, warning_blocks)?;
+                if notified.insert(offer.txid.clone(), notice) == Some(notice) {
+                    return None;
+                }
+                Some(OfferExpiryEvent {
+                    notice,
+                    offer: offer.clone(),
+                    height,
+                    blocks_remaining: offer.blockexpiry.saturating_sub(height),
+                    reclaim: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Expired offers to close now, each returned at most once while it stays open
+    pub fn take_reclaimable(&self, offers: &[MarketOffer], height: u64) -> Vec<String> {
+        let config = self.config();
+        if !config.auto_close {
+            return Vec::new();
+        }
+        let Ok(mut reclaimed) = self.reclaimed.write() else {
+            return Vec::new();
+        };
+        offers.iter()
+            .filter(|o| offer_notice(o, height, 0) == Some(OfferNotice::Expired))
+            .filter(|o| reclaimed.insert(o.txid.clone()))
+            .map(|o| o.txid.clone())
+            .collect()
+    }
+
+    /// Mark the background poller as running, returning its generation or `None` if it already was
+    pub fn start(&self) -> Option<u64> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        Some(self.generation.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Whether the poller started as `generation` should keep going
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.running.load(Ordering::SeqCst) && self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+impl VerusRpcClient {
+    /// Check the wallet's offers against the tip, closing expired ones if configured
+    pub async fn poll_offer_expiry(&self, monitor: &OfferMonitor) -> Result<Vec<OfferExpiryEvent>, RpcError> {
+        let (native_id, height) = self.offer_context().await?;
+        let offers = parse_open_offers(&self.list_open_offers().await?, &native_id, height);
+        let mut events = monitor.review(&offers, height);
+
+        let to_close = monitor.take_reclaimable(&offers, height);
+        let destination = monitor.config().destination_address.unwrap_or_default();
+        if to_close.is_empty() || destination.is_empty() {
+            return Ok(events);
+        }
+
+        let reclaim = match self.close_offers(to_close.clone(), Some(&destination)).await {
+            Ok(()) => OfferReclaim { destination, closed: true, error: None },
+            Err(e) => OfferReclaim { destination, closed: false, error: Some(e.to_string()) },
+        };
+        for txid in &to_close {
+            match events.iter_mut().find(|e| &e.offer.txid == txid) {
+                Some(event) => event.reclaim = Some(reclaim.clone()),
+                // Expired before auto-close was enabled, so already reported once
+                None => if let Some(offer) = offers.iter().find(|o| &o.txid == txid) {
+                    events.push(OfferExpiryEvent {
+                        notice: OfferNotice::Expired,
+                        offer: offer.clone(),
+                        height,
+                        blocks_remaining: 0,
+                        reclaim: Some(reclaim.clone()),
+                    });
+                },
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::amount::Amount;
+    use crate::rpc::offers::{OfferAsset, OfferKind};
+
+    fn offer(txid: &str, blockexpiry: u64) -> MarketOffer {
+        let offer = OfferAsset::Identity { identityid: "iA".to_string(), name: "alice".to_string() };
+        let accept = OfferAsset::Currency { currencyid: "iB".to_string(), name: None, amount: Amount::from_sats(1) };
+        MarketOffer {
+            txid: txid.to_string(),
+            vout: None,
+            category: None,
+            kind: OfferKind::of(&offer, &accept),
+            offer,
+            accept,
+            blockexpiry,
+            expired: false,
+            price: None,
+        }
+    }
+
+    #[test]
+    fn test_offer_notice() {
+        assert_eq!(offer_notice(&offer("a", 1020), 1000, 10), None);
+        assert_eq!(offer_notice(&offer("a", 1010), 1000, 10), Some(OfferNotice::Expiring));
+        assert_eq!(offer_notice(&offer("a", 1000), 1000, 10), Some(OfferNotice::Expired));
+    }
+
+    #[test]
+    fn test_review_reports_each_notice_once() {
+        let monitor = OfferMonitor::default();
+        let offers = vec![offer("a", 1005), offer("b", 1100)];
+
+        let events = monitor.review(&offers, 1000);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].notice, events[0].blocks_remaining), (OfferNotice::Expiring, 5));
+        assert!(monitor.review(&offers, 1001).is_empty());
+
+        let expired = monitor.review(&offers, 1005);
+        assert_eq!(expired[0].notice, OfferNotice::Expired);
+
+        // Closed offers are forgotten, so a reused txid would be reported again
+        assert!(monitor.review(&offers[1..], 1006).is_empty());
+        assert_eq!(monitor.review(&offers, 1006).len(), 1);
+    }
+
+    #[test]
+    fn test_reclaim_needs_destination_and_runs_once() {
+        let monitor = OfferMonitor::default();
+        let offers = vec![offer("a", 900), offer("b", 1100)];
+        assert!(monitor.take_reclaimable(&offers, 1000).is_empty());
+
+        assert!(monitor.configure(OfferMonitorConfig { auto_close: true, ..Default::default() }).is_err());
+        monitor.configure(OfferMonitorConfig {
+            auto_close: true,
+            destination_address: Some("alice@".to_string()),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(monitor.take_reclaimable(&offers, 1000), vec!["a".to_string()]);
+        assert!(monitor.take_reclaimable(&offers, 1001).is_empty());
+    }
+
+    #[test]
+    fn test_restart_retires_previous_poller() {
+        let monitor = OfferMonitor::default();
+        let first = monitor.start().unwrap();
+        assert_eq!(monitor.start(), None);
+        assert!(monitor.is_current(first));
+
+        monitor.stop();
+        let second = monitor.start().unwrap();
+        assert!(!monitor.is_current(first));
+        assert!(monitor.is_current(second));
+    }
+}
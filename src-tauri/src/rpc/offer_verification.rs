@@ -0,0 +1,291 @@
+// Pre-take Offer Verification
+use crate::rpc::{VerusRpcClient, RpcError, Identity};
+use crate::rpc::identity_audit::AuditSeverity;
+use crate::rpc::identity_history::{IDENTITY_FLAG_LOCKED, IDENTITY_FLAG_REVOKED};
+use crate::rpc::offers::{parse_get_offers, MarketOffer, OfferAsset};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Offers expiring within this many blocks may not confirm in time to be taken
+const EXPIRY_WARNING_BLOCKS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferWarningCode {
+    NotListed,
+    TermsChanged,
+    Spent,
+    SpendUnknown,
+    Expired,
+    ExpiringSoon,
+    IdentityRevoked,
+    IdentityTimelocked,
+    ThirdPartyRevocation,
+    ThirdPartyRecovery,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferWarning {
+    pub severity: AuditSeverity,
+    pub code: OfferWarningCode,
+    pub message: String,
+}
+
+impl OfferWarning {
+    fn new(severity: AuditSeverity, code: OfferWarningCode, message: impl Into<String>) -> Self {
+        Self { severity, code, message: message.into() }
+    }
+}
+
+/// Current state of an identity the taker would receive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferedIdentityState {
+    pub name: String,
+    pub identityaddress: String,
+    pub status: String,
+    pub flags: u32,
+    pub timelock: u64,
+    pub revocationauthority: String,
+    pub recoveryauthority: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferVerification {
+    pub txid: String,
+    pub height: u64,
+    /// The offer as currently listed, `None` once it was taken, closed or expired
+    pub current: Option<MarketOffer>,
+    /// Whether the output the offer spends is gone; `None` when it could not be checked
+    pub spent: Option<bool>,
+    pub identity: Option<OfferedIdentityState>,
+    /// No warning is critical
+    pub safe: bool,
+    /// Most severe first
+    pub warnings: Vec<OfferWarning>,
+}
+
+/// Output a decoded offer transaction spends, as returned by `getoffers` with `withtx`
+///
+/// The first input is the funds or identity the offer puts up.
+pub fn offer_input(decoded_tx: &Value) -> Option<(String, u32)> {
+    let input = decoded_tx.get("vin")?.as_array()?.first()?;
+    Some((input.get("txid")?.as_str()?.to_string(), input.get("vout")?.as_u64()? as u32))
+}
+
+/// Warnings about the offer itself, comparing what the taker saw with what is listed now
+pub fn offer_warnings(
+    expected: &MarketOffer,
+    current: Option<&MarketOffer>,
+    spent: Option<bool>,
+    height: u64
+) -> Vec<OfferWarning> {
+    use AuditSeverity::*;
+    use OfferWarningCode::*;
+    let mut warnings = Vec::new();
+
+    match current {
+        None => warnings.push(OfferWarning::new(Critical, NotListed, "Offer is no longer listed; it was taken, closed or has expired")),
+        Some(current) if current.offer != expected.offer || current.accept != expected.accept => {
+            warnings.push(OfferWarning::new(Critical, TermsChanged, "Offer terms differ from the ones shown"));
+        }
+        Some(_) => {}
+    }
+
+    match spent {
+        Some(true) => warnings.push(OfferWarning::new(Critical, Spent, "Offer already spent")),
+        Some(false) => {}
+        None => warnings.push(OfferWarning::new(Warning, SpendUnknown, "Could not check whether the offer is still unspent")),
+    }
+
+    let blockexpiry = current.map(|c| c.blockexpiry).unwrap_or(expected.blockexpiry);
+    if blockexpiry <= height {
+        warnings.push(OfferWarning::new(Critical, Expired, format!("Offer expired at block {}", blockexpiry)));
+    } else if blockexpiry - height <= EXPIRY_WARNING_BLOCKS {
+        warnings.push(OfferWarning::new(
+            Warning,
+            ExpiringSoon,
+            format!("Offer expires in {} blocks and may expire before the take confirms", blockexpiry - height),
+        ));
+    }
+    warnings
+}
+
+/// Warnings about an identity the taker would receive
+pub fn identity_warnings(identity: &OfferedIdentityState, height: u64) -> Vec<OfferWarning> {
+    use AuditSeverity::*;
+    use OfferWarningCode::*;
+    let mut warnings = Vec::new();
+
+    if identity.flags & IDENTITY_FLAG_REVOKED != 0 || identity.status == "revoked" {
+        warnings.push(OfferWarning::new(Critical, IdentityRevoked, format!("ID {} is revoked", identity.name)));
+    }
+    if identity.flags & IDENTITY_FLAG_LOCKED != 0 {
+        warnings.push(OfferWarning::new(
+            Warning,
+            IdentityTimelocked,
+            format!("ID is timelocked with an unlock delay of {} blocks", identity.timelock),
+        ));
+    } else if identity.timelock > height {
+        warnings.push(OfferWarning::new(
+            Warning,
+            IdentityTimelocked,
+            format!("ID is timelocked until block {}", identity.timelock),
+        ));
+    }
+
+    // Authorities carry over unless the taker's accepted definition replaces them
+    if identity.revocationauthority != identity.identityaddress {
+        warnings.push(OfferWarning::new(
+            Warning,
+            ThirdPartyRevocation,
+            format!("Revocation authority is a third party ({}); set your own when taking the offer", identity.revocationauthority),
+        ));
+    }
+    if identity.recoveryauthority != identity.identityaddress {
+        warnings.push(OfferWarning::new(
+            Warning,
+            ThirdPartyRecovery,
+            format!("Recovery authority is a third party ({}); set your own when taking the offer", identity.recoveryauthority),
+        ));
+    }
+    warnings
+}
+
+fn identity_state(identity: &Identity) -> OfferedIdentityState {
+    let details = &identity.identity;
+    OfferedIdentityState {
+        name: details.name.clone(),
+        identityaddress: details.identityaddress.clone(),
+        status: identity.status.clone(),
+        flags: details.flags,
+        timelock: details.timelock,
+        revocationauthority: details.revocationauthority.clone(),
+        recoveryauthority: details.recoveryauthority.clone(),
+    }
+}
+
+impl VerusRpcClient {
+    /// Whether the output an offer spends is still unspent, including the mempool
+    async fn offer_spent(&self, entry: Option<&Value>) -> Result<Option<bool>, RpcError> {
+        let tx = entry.and_then(|e| e.get("offer")).and_then(|o| o.get("tx"));
+        let decoded = match tx {
+            Some(Value::String(hex)) => self.decode_raw_transaction(hex).await.ok(),
+            Some(decoded) => Some(decoded.clone()),
+            None => None,
+        };
+        let Some((txid, vout)) = decoded.as_ref().and_then(offer_input) else {
+            return Ok(None);
+        };
+        Ok(Some(self.get_tx_out(&txid, vout).await?.is_none()))
+    }
+
+    /// Re-fetch an offer just before taking it and report anything that makes it unsafe to take
+    pub async fn verify_offer(&self, expected: &MarketOffer) -> Result<OfferVerification, RpcError> {
+        let (native_id, height) = self.offer_context().await?;
+        let is_currency = matches!(expected.offer, OfferAsset::Currency { .. });
+        let result = self.get_offers(expected.offer.id(), Some(is_currency), Some(true)).await?;
+
+        let current = parse_get_offers(&result, &native_id, height)
+            .into_iter()
+            .find(|o| o.txid == expected.txid);
+        let entry = result.as_object().into_iter()
+            .flat_map(|categories| categories.values())
+            .filter_map(|entries| entries.as_array())
+            .flatten()
+            .find(|e| e.get("offer").and_then(|o| o.get("txid")).and_then(|t| t.as_str()) == Some(&expected.txid));
+        let spent = self.offer_spent(entry).await?;
+
+        let mut warnings = offer_warnings(expected, current.as_ref(), spent, height);
+        let identity = match &expected.offer {
+            OfferAsset::Identity { identityid, .. } => {
+                let identity: Identity = serde_json::from_value(
+                    self.get_identity(identityid, None, None, None, None).await?
+                )?;
+                Some(identity_state(&identity))
+            }
+            OfferAsset::Currency { .. } => None,
+        };
+        if let Some(identity) = &identity {
+            warnings.extend(identity_warnings(identity, height));
+        }
+        warnings.sort_by_key(|w| std::cmp::Reverse(w.severity));
+
+        Ok(OfferVerification {
+            txid: expected.txid.clone(),
+            height,
+            safe: warnings.iter().all(|w| w.severity != AuditSeverity::Critical),
+            current,
+            spent,
+            identity,
+            warnings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::amount::Amount;
+    use crate::rpc::offers::OfferKind;
+    use crate::rpc::test_util::ALICE;
+    use serde_json::json;
+
+    fn offer(blockexpiry: u64, price: i64) -> MarketOffer {
+        MarketOffer {
+            txid: "t1".to_string(),
+            vout: None,
+            category: None,
+            kind: OfferKind::IdForCurrency,
+            offer: OfferAsset::Identity { identityid: ALICE.to_string(), name: "alice".to_string() },
+            accept: OfferAsset::Currency { currencyid: "iVRSC".to_string(), name: None, amount: Amount::from_sats(price) },
+            blockexpiry,
+            expired: false,
+            price: None,
+        }
+    }
+
+    fn codes(warnings: &[OfferWarning]) -> Vec<OfferWarningCode> {
+        warnings.iter().map(|w| w.code).collect()
+    }
+
+    #[test]
+    fn test_offer_warnings() {
+        let shown = offer(1100, 100);
+        assert!(offer_warnings(&shown, Some(&shown), Some(false), 1000).is_empty());
+        assert_eq!(
+            codes(&offer_warnings(&shown, Some(&offer(1003, 200)), Some(true), 1000)),
+            vec![OfferWarningCode::TermsChanged, OfferWarningCode::Spent, OfferWarningCode::ExpiringSoon]
+        );
+        assert_eq!(
+            codes(&offer_warnings(&shown, None, None, 1100)),
+            vec![OfferWarningCode::NotListed, OfferWarningCode::SpendUnknown, OfferWarningCode::Expired]
+        );
+    }
+
+    #[test]
+    fn test_identity_warnings() {
+        let mut identity = OfferedIdentityState {
+            name: "alice".to_string(),
+            identityaddress: ALICE.to_string(),
+            status: "active".to_string(),
+            flags: 0,
+            timelock: 0,
+            revocationauthority: ALICE.to_string(),
+            recoveryauthority: ALICE.to_string(),
+        };
+        assert!(identity_warnings(&identity, 1000).is_empty());
+
+        identity.timelock = 1200;
+        identity.revocationauthority = "iOther".to_string();
+        let warnings = identity_warnings(&identity, 1000);
+        assert_eq!(codes(&warnings), vec![OfferWarningCode::IdentityTimelocked, OfferWarningCode::ThirdPartyRevocation]);
+        assert_eq!(warnings[0].message, "ID is timelocked until block 1200");
+    }
+
+    #[test]
+    fn test_offer_input() {
+        let tx = json!({ "vin": [{ "txid": "funding", "vout": 1 }], "vout": [] });
+        assert_eq!(offer_input(&tx), Some(("funding".to_string(), 1)));
+        assert_eq!(offer_input(&json!({ "vin": [] })), None);
+    }
+}
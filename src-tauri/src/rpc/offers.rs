@@ -0,0 +1,354 @@
+// Typed Marketplace Offers (getoffers / listopenoffers)
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::amount::Amount;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// One side of an offer: what the maker gives or what they want in return
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OfferAsset {
+    Currency {
+        currencyid: String,
+        /// Friendly name, when the daemon reports one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        amount: Amount,
+    },
+    Identity {
+        identityid: String,
+        name: String,
+    },
+}
+
+impl OfferAsset {
+    fn currency(&self) -> Option<(&str, Amount)> {
+        match self {
+            OfferAsset::Currency { currencyid, amount, .. } => Some((currencyid, *amount)),
+            OfferAsset::Identity { .. } => None,
+        }
+    }
+
+    /// i-address of the currency or identity
+    pub fn id(&self) -> &str {
+        match self {
+            OfferAsset::Currency { currencyid, .. } => currencyid,
+            OfferAsset::Identity { identityid, .. } => identityid,
+        }
+    }
+}
+
+/// Which kinds of asset change hands, maker's side first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferKind {
+    CurrencyForId,
+    IdForCurrency,
+    CurrencyForCurrency,
+    IdForId,
+}
+
+impl OfferKind {
+    pub(crate) fn of(offer: &OfferAsset, accept: &OfferAsset) -> Self {
+        match (offer, accept) {
+            (OfferAsset::Currency { .. }, OfferAsset::Identity { .. }) => OfferKind::CurrencyForId,
+            (OfferAsset::Identity { .. }, OfferAsset::Currency { .. }) => OfferKind::IdForCurrency,
+            (OfferAsset::Currency { .. }, OfferAsset::Currency { .. }) => OfferKind::CurrencyForCurrency,
+            (OfferAsset::Identity { .. }, OfferAsset::Identity { .. }) => OfferKind::IdForId,
+        }
+    }
+}
+
+/// An offer's price as units of `quote` per one unit of `base`
+///
+/// When an identity changes hands it is the base, priced in the currency on the
+/// other side; between two currencies the maker's offered currency is the base.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferPrice {
+    pub base: String,
+    pub quote: String,
+    pub price: f64,
+}
+
+impl OfferPrice {
+    pub fn of(offer: &OfferAsset, accept: &OfferAsset) -> Option<Self> {
+        let (base, quote, price) = match (offer, accept) {
+            (OfferAsset::Identity { identityid, .. }, OfferAsset::Currency { currencyid, amount, .. })
+            | (OfferAsset::Currency { currencyid, amount, .. }, OfferAsset::Identity { identityid, .. }) => {
+                (identityid.clone(), currencyid.clone(), amount.to_f64())
+            }
+            (OfferAsset::Currency { .. }, OfferAsset::Currency { .. }) => {
+                let (base, offered) = offer.currency()?;
+                let (quote, wanted) = accept.currency()?;
+                if !offered.is_positive() {
+                    return None;
+                }
+                (base.to_string(), quote.to_string(), wanted.to_f64() / offered.to_f64())
+            }
+            (OfferAsset::Identity { .. }, OfferAsset::Identity { .. }) => return None,
+        };
+        Some(Self { base, quote, price })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketOffer {
+    pub txid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vout: Option<u32>,
+    /// `getoffers` group, e.g. `currency_<id>_offers_in_currency_<id>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub kind: OfferKind,
+    /// What the maker gives up
+    pub offer: OfferAsset,
+    /// What the maker wants in return
+    pub accept: OfferAsset,
+    pub blockexpiry: u64,
+    pub expired: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<OfferPrice>,
+}
+
+impl MarketOffer {
+    fn new(
+        txid: String,
+        vout: Option<u32>,
+        category: Option<String>,
+        offer: OfferAsset,
+        accept: OfferAsset,
+        blockexpiry: u64,
+        height: u64
+    ) -> Self {
+        Self {
+            txid,
+            vout,
+            category,
+            kind: OfferKind::of(&offer, &accept),
+            price: OfferPrice::of(&offer, &accept),
+            offer,
+            accept,
+            blockexpiry,
+            expired: blockexpiry <= height,
+        }
+    }
+}
+
+/// Order two offers by price when they trade the same pair
+pub fn compare_prices(a: &MarketOffer, b: &MarketOffer) -> Option<Ordering> {
+    match (&a.price, &b.price) {
+        (Some(pa), Some(pb)) if pa.base == pb.base && pa.quote == pb.quote => pa.price.partial_cmp(&pb.price),
+        _ => None,
+    }
+}
+
+/// Group offers by pair, cheapest first within each, unpriced offers last
+pub fn sort_offers(offers: &mut [MarketOffer]) {
+    offers.sort_by(|a, b| {
+        let pair = |o: &MarketOffer| o.price.as_ref().map(|p| (p.base.clone(), p.quote.clone()));
+        match (pair(a), pair(b)) {
+            (Some(pa), Some(pb)) => pa.cmp(&pb)
+                .then_with(|| compare_prices(a, b).unwrap_or(Ordering::Equal)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a.blockexpiry.cmp(&b.blockexpiry))
+    });
+}
+
+/// Read one side of an offer in either daemon format
+///
+/// `getoffers` reports identities as `{name, identityid}` and currencies as
+/// `{"<currencyid>": amount}`; `listopenoffers` reports the transaction outputs,
+/// with `identityprimary`, `currencyvalues` and `nativeout`.
+pub fn parse_offer_asset(side: &Value, native_id: &str) -> Option<OfferAsset> {
+    let object = side.as_object()?;
+
+    if let (Some(name), Some(id)) = (side.get("name").and_then(|v| v.as_str()), side.get("identityid").and_then(|v| v.as_str())) {
+        return Some(OfferAsset::Identity { identityid: id.to_string(), name: name.to_string() });
+    }
+    if let Some(primary) = side.get("identityprimary") {
+        let name = primary.get("name")?.as_str()?;
+        let id = primary.get("identityaddress").or_else(|| primary.get("identityid"))?.as_str()?;
+        return Some(OfferAsset::Identity { identityid: id.to_string(), name: name.to_string() });
+    }
+
+    let friendly_name = side.get("reserve_balance")
+        .and_then(|b| b.as_object())
+        .and_then(|b| b.keys().next().cloned());
+    let currency_values = ["reserveoutput", "commitmenthash"].iter()
+        .find_map(|key| side.get(*key).and_then(|o| o.get("currencyvalues")))
+        .and_then(|v| v.as_object());
+    if let Some((id, amount)) = currency_values.and_then(|values| values.iter().next()) {
+        return Some(OfferAsset::Currency {
+            currencyid: id.clone(),
+            name: friendly_name,
+            amount: Amount::deserialize(amount).ok()?,
+        });
+    }
+    if let Some(native) = side.get("nativeout").and_then(|v| Amount::deserialize(v).ok()).filter(|a| a.is_positive()) {
+        return Some(OfferAsset::Currency { currencyid: native_id.to_string(), name: None, amount: native });
+    }
+
+    object.iter()
+        .filter(|(key, value)| key.starts_with('i') && value.is_number())
+        .find_map(|(id, amount)| Some(OfferAsset::Currency {
+            currencyid: id.clone(),
+            name: None,
+            amount: Amount::deserialize(amount).ok()?,
+        }))
+}
+
+/// Flatten a `getoffers` result, which groups offers by category
+pub fn parse_get_offers(result: &Value, native_id: &str, height: u64) -> Vec<MarketOffer> {
+    let mut offers = Vec::new();
+    for (category, entries) in result.as_object().into_iter().flatten() {
+        for entry in entries.as_array().into_iter().flatten() {
+            let Some(details) = entry.get("offer") else { continue };
+            let sides = (
+                details.get("offer").and_then(|s| parse_offer_asset(s, native_id)),
+                details.get("accept").and_then(|s| parse_offer_asset(s, native_id)),
+                details.get("txid").and_then(|t| t.as_str()),
+            );
+            if let (Some(offer), Some(accept), Some(txid)) = sides {
+                offers.push(MarketOffer::new(
+                    txid.to_string(),
+                    details.get("vout").and_then(|v| v.as_u64()).map(|v| v as u32),
+                    Some(category.clone()),
+                    offer,
+                    accept,
+                    details.get("blockexpiry").and_then(|b| b.as_u64()).unwrap_or(0),
+                    height,
+                ));
+            }
+        }
+    }
+    offers
+}
+
+/// Parse `listopenoffers`, whose entries describe the wallet's offer transactions
+pub fn parse_open_offers(result: &Value, native_id: &str, height: u64) -> Vec<MarketOffer> {
+    result.as_array().into_iter().flatten()
+        .filter_map(|entry| {
+            let offer = parse_offer_asset(entry.get("offer")?, native_id)?;
+            let accept = parse_offer_asset(entry.get("for")?, native_id)?;
+            let blockexpiry = entry.get("expires").or_else(|| entry.get("blockexpiry")).and_then(|b| b.as_u64()).unwrap_or(0);
+            let mut parsed = MarketOffer::new(
+                entry.get("txid")?.as_str()?.to_string(),
+                entry.get("vout").and_then(|v| v.as_u64()).map(|v| v as u32),
+                None,
+                offer,
+                accept,
+                blockexpiry,
+                height,
+            );
+            if let Some(expired) = entry.get("expired").and_then(|e| e.as_bool()) {
+                parsed.expired = expired;
+            }
+            Some(parsed)
+        })
+        .collect()
+}
+
+impl VerusRpcClient {
+    /// Chain tip and native currency id, needed to interpret offers
+    pub(crate) async fn offer_context(&self) -> Result<(String, u64), RpcError> {
+        let info = self.get_info(None).await?;
+        let native_id = info.chainid.unwrap_or_default();
+        let height = match info.blocks {
+            Some(blocks) => blocks,
+            None => self.get_block_count(None).await?,
+        };
+        Ok((native_id, height))
+    }
+
+    /// All offers for a currency or identity, sorted by pair and price
+    pub async fn get_market_offers(
+        &self,
+        currency_or_id: &str,
+        is_currency: bool
+    ) -> Result<Vec<MarketOffer>, RpcError> {
+        let (native_id, height) = self.offer_context().await?;
+        let result = self.get_offers(currency_or_id, Some(is_currency), None).await?;
+        let mut offers = parse_get_offers(&result, &native_id, height);
+        sort_offers(&mut offers);
+        Ok(offers)
+    }
+
+    /// The wallet's own offers, expired ones included
+    pub async fn list_wallet_offers(&self) -> Result<Vec<MarketOffer>, RpcError> {
+        let (native_id, height) = self.offer_context().await?;
+        let result = self.list_open_offers().await?;
+        Ok(parse_open_offers(&result, &native_id, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{amount, VRSC, TBTC, ALICE};
+    use serde_json::json;
+
+    fn get_offers_result() -> Value {
+        json!({
+            "currency_iS8TfRPfVpKo5FVfSUzfHBQxo9KuzpnqLU_offers_in_currency_i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV": [
+                { "currencyid": TBTC, "price": 250.0, "offer": {
+                    "accept": { VRSC: 25.0 }, "offer": { TBTC: 0.1 }, "blockexpiry": 1200, "txid": "t1" } },
+                { "currencyid": TBTC, "price": 200.0, "offer": {
+                    "accept": { VRSC: 40.0 }, "offer": { TBTC: 0.2 }, "blockexpiry": 900, "txid": "t2" } }
+            ],
+            "id_iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq_for_currency_i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV": [
+                { "identityid": ALICE, "price": 100.0, "offer": {
+                    "accept": { VRSC: 100.0 },
+                    "offer": { "name": "alice", "identityid": ALICE, "systemid": VRSC },
+                    "blockexpiry": 1500, "txid": "t3" } }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_parse_get_offers() {
+        let mut offers = parse_get_offers(&get_offers_result(), VRSC, 1000);
+        sort_offers(&mut offers);
+
+        let txids: Vec<_> = offers.iter().map(|o| o.txid.as_str()).collect();
+        assert_eq!(txids, vec!["t3", "t2", "t1"]);
+
+        let identity = &offers[0];
+        assert_eq!(identity.kind, OfferKind::IdForCurrency);
+        assert_eq!(identity.price, Some(OfferPrice { base: ALICE.to_string(), quote: VRSC.to_string(), price: 100.0 }));
+
+        assert_eq!(offers[1].kind, OfferKind::CurrencyForCurrency);
+        assert!(offers[1].expired);
+        assert_eq!(offers[1].price.as_ref().unwrap().price, 200.0);
+        assert_eq!(compare_prices(&offers[1], &offers[2]), Some(Ordering::Less));
+        assert_eq!(compare_prices(&offers[0], &offers[1]), None);
+    }
+
+    #[test]
+    fn test_parse_open_offers() {
+        let result = json!([
+            { "txid": "w1", "expires": 1100,
+              "offer": { "nativeout": 5.0 },
+              "for": { "identityprimary": { "name": "alice", "identityaddress": ALICE } } },
+            { "txid": "w2", "expires": 800,
+              "offer": { "reserve_balance": { "tBTC.vETH": 0.5 }, "commitmenthash": { "currencyvalues": { TBTC: 0.5 } } },
+              "for": { "reserveoutput": { "currencyvalues": { VRSC: 60.0 } } } }
+        ]);
+        let offers = parse_open_offers(&result, VRSC, 1000);
+
+        assert_eq!(offers[0].kind, OfferKind::CurrencyForId);
+        assert_eq!(offers[0].offer, OfferAsset::Currency { currencyid: VRSC.to_string(), name: None, amount: amount("5") });
+        assert!(!offers[0].expired);
+
+        assert_eq!(offers[1].offer, OfferAsset::Currency {
+            currencyid: TBTC.to_string(),
+            name: Some("tBTC.vETH".to_string()),
+            amount: amount("0.5"),
+        });
+        assert_eq!(offers[1].price.as_ref().unwrap().price, 120.0);
+        assert!(offers[1].expired);
+    }
+}
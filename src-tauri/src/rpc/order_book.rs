@@ -0,0 +1,291 @@
+// Marketplace Order Book
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::amount::{Amount, SATOSHIS_PER_COIN};
+use crate::rpc::offers::{MarketOffer, OfferAsset};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookSide {
+    /// The maker pays to receive the asset
+    Bid,
+    /// The maker gives up the asset
+    Ask,
+}
+
+/// An offer priced in the book's quote currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookEntry {
+    /// Quote units per unit of the asset
+    pub price: f64,
+    /// Units of the asset; always 1 for an identity
+    pub size: Amount,
+    /// `price * size`, in the quote currency
+    pub total: f64,
+    /// Size of this and every better-priced entry on the same side
+    pub cumulative_size: Amount,
+    /// Currency the maker actually priced the offer in
+    pub priced_in: String,
+    pub offer: MarketOffer,
+}
+
+/// An offer left out of the book, with the reason
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnpricedOffer {
+    pub txid: String,
+    pub reason: String,
+}
+
+/// What a book is for and what it is priced in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookMarket {
+    pub asset: String,
+    pub asset_id: String,
+    pub is_currency: bool,
+    pub quote: String,
+    pub quote_id: String,
+    pub height: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    #[serde(flatten)]
+    pub market: OrderBookMarket,
+    /// Highest price first
+    pub bids: Vec<OrderBookEntry>,
+    /// Lowest price first
+    pub asks: Vec<OrderBookEntry>,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub spread: Option<f64>,
+    pub bid_depth: Amount,
+    pub ask_depth: Amount,
+    /// Quote value of every bid
+    pub bid_total: f64,
+    /// Quote value of every ask
+    pub ask_total: f64,
+    pub unpriced: Vec<UnpricedOffer>,
+}
+
+/// Which side of the book an offer is on, with its size and price in the other side's currency
+///
+/// Returns the reason instead when the offer does not trade the asset for a currency.
+pub fn classify_offer(offer: &MarketOffer, asset_id: &str) -> Result<(BookSide, Amount, String, f64), String> {
+    let one = Amount::from_sats(SATOSHIS_PER_COIN);
+    let (side, asset, other) = if offer.offer.id() == asset_id {
+        (BookSide::Ask, &offer.offer, &offer.accept)
+    } else if offer.accept.id() == asset_id {
+        (BookSide::Bid, &offer.accept, &offer.offer)
+    } else {
+        return Err("Offer does not trade this asset".to_string());
+    };
+
+    let OfferAsset::Currency { currencyid: priced_in, amount: price_amount, .. } = other else {
+        return Err("Offer is for an identity rather than a currency".to_string());
+    };
+    let size = match asset {
+        OfferAsset::Currency { amount, .. } => *amount,
+        OfferAsset::Identity { .. } => one,
+    };
+    if !size.is_positive() {
+        return Err("Offer has no size".to_string());
+    }
+    Ok((side, size, priced_in.clone(), price_amount.to_f64() / size.to_f64()))
+}
+
+/// Bids highest first and asks lowest first, earlier expiry first at the same price
+fn sort_side(entries: &mut [OrderBookEntry], side: BookSide) {
+    entries.sort_by(|a, b| {
+        let by_price = a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal);
+        match side {
+            BookSide::Bid => by_price.reverse(),
+            BookSide::Ask => by_price,
+        }
+        .then_with(|| a.offer.blockexpiry.cmp(&b.offer.blockexpiry))
+    });
+    let mut cumulative = Amount::ZERO;
+    for entry in entries.iter_mut() {
+        cumulative = cumulative.checked_add(entry.size).unwrap_or(cumulative);
+        entry.cumulative_size = cumulative;
+    }
+}
+
+/// Split offers into a sorted book
+///
+/// `rates` holds quote units per unit of each currency offers are priced in; offers in a
+/// currency without a rate are reported as unpriced.
+pub fn build_order_book(
+    market: OrderBookMarket,
+    offers: Vec<MarketOffer>,
+    rates: &HashMap<String, f64>
+) -> OrderBook {
+    let mut bids = Vec::new();
+    let mut asks = Vec::new();
+    let mut unpriced = Vec::new();
+
+    for offer in offers.into_iter().filter(|o| !o.expired) {
+        let (side, size, priced_in, price) = match classify_offer(&offer, &market.asset_id) {
+            Ok(classified) => classified,
+            Err(reason) => {
+                unpriced.push(UnpricedOffer { txid: offer.txid, reason });
+                continue;
+            }
+        };
+        let Some(rate) = rates.get(&priced_in) else {
+            unpriced.push(UnpricedOffer {
+                txid: offer.txid,
+                reason: format!("No conversion from {} to {}", priced_in, market.quote),
+            });
+            continue;
+        };
+
+        let price = price * rate;
+        let entry = OrderBookEntry {
+            price,
+            size,
+            total: price * size.to_f64(),
+            cumulative_size: size,
+            priced_in,
+            offer,
+        };
+        match side {
+            BookSide::Bid => bids.push(entry),
+            BookSide::Ask => asks.push(entry),
+        }
+    }
+    sort_side(&mut bids, BookSide::Bid);
+    sort_side(&mut asks, BookSide::Ask);
+
+    let best_bid = bids.first().map(|e| e.price);
+    let best_ask = asks.first().map(|e| e.price);
+    OrderBook {
+        market,
+        spread: best_bid.zip(best_ask).map(|(bid, ask)| ask - bid),
+        best_bid,
+        best_ask,
+        bid_depth: bids.last().map(|e| e.cumulative_size).unwrap_or(Amount::ZERO),
+        ask_depth: asks.last().map(|e| e.cumulative_size).unwrap_or(Amount::ZERO),
+        bid_total: bids.iter().map(|e| e.total).sum(),
+        ask_total: asks.iter().map(|e| e.total).sum(),
+        bids,
+        asks,
+        unpriced,
+    }
+}
+
+impl VerusRpcClient {
+    /// Quote units per unit of `currency`, from the best conversion route
+    ///
+    /// Uses the basket's spot price when known, so fees and the size of the probe
+    /// conversion do not skew the book.
+    async fn quote_rate(&self, currency_id: &str, quote_id: &str) -> Result<f64, RpcError> {
+        if currency_id == quote_id {
+            return Ok(1.0);
+        }
+        let one = Amount::from_sats(SATOSHIS_PER_COIN);
+        let routes = self.find_conversion_routes(currency_id, quote_id, one).await?;
+        let best = routes.routes.first().ok_or_else(|| {
+            RpcError::ValidationError(format!("No conversion route from {} to {}", currency_id, quote_id))
+        })?;
+        Ok(best.spot_rate.unwrap_or_else(|| best.estimated_output.to_f64()))
+    }
+
+    /// Every offer for a currency or identity as bids and asks priced in `quote`
+    ///
+    /// The quote currency defaults to the chain's native currency.
+    pub async fn get_order_book(
+        &self,
+        currency_or_id: &str,
+        is_currency: bool,
+        quote: Option<&str>
+    ) -> Result<OrderBook, RpcError> {
+        let (native_id, height) = self.offer_context().await?;
+        let asset_id = if is_currency {
+            self.get_currency(currency_or_id, None).await?.currencyid
+        } else {
+            self.resolve_identity(currency_or_id, None).await?.identityaddress
+        };
+        let quote_id = match quote.filter(|q| !q.is_empty()) {
+            Some(quote) => self.get_currency(quote, None).await?.currencyid,
+            None => native_id.clone(),
+        };
+
+        let offers = self.get_market_offers(currency_or_id, is_currency).await?;
+        let mut rates = HashMap::new();
+        for offer in &offers {
+            let Ok((_, _, priced_in, _)) = classify_offer(offer, &asset_id) else { continue };
+            if rates.contains_key(&priced_in) {
+                continue;
+            }
+            // Currencies without a route are left out and reported as unpriced
+            if let Ok(rate) = self.quote_rate(&priced_in, &quote_id).await {
+                rates.insert(priced_in, rate);
+            }
+        }
+
+        let market = OrderBookMarket {
+            asset: currency_or_id.to_string(),
+            asset_id,
+            is_currency,
+            quote: quote.unwrap_or(&native_id).to_string(),
+            quote_id,
+            height,
+        };
+        Ok(build_order_book(market, offers, &rates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::offers::{parse_get_offers, sort_offers};
+    use crate::rpc::test_util::{amount, VRSC, TBTC, DAI};
+    use serde_json::json;
+
+    fn entry(offer: f64, offer_id: &str, accept: f64, accept_id: &str, txid: &str) -> serde_json::Value {
+        json!({ "offer": { "offer": { offer_id: offer }, "accept": { accept_id: accept }, "blockexpiry": 2000, "txid": txid } })
+    }
+
+    #[test]
+    fn test_order_book() {
+        let result = json!({
+            "tbtc": [
+                entry(0.1, TBTC, 25.0, VRSC, "ask-250"),
+                entry(0.2, TBTC, 48.0, VRSC, "ask-240"),
+                entry(30.0, VRSC, 0.15, TBTC, "bid-200"),
+                entry(207.0, DAI, 0.3, TBTC, "bid-dai-230"),
+                entry(100.0, "iUnpriced", 0.1, TBTC, "bid-unpriced"),
+            ]
+        });
+        let mut offers = parse_get_offers(&result, VRSC, 1000);
+        sort_offers(&mut offers);
+        let rates = HashMap::from([(VRSC.to_string(), 1.0), (DAI.to_string(), 1.0 / 3.0)]);
+        let market = OrderBookMarket {
+            asset: "tBTC".to_string(),
+            asset_id: TBTC.to_string(),
+            is_currency: true,
+            quote: "VRSC".to_string(),
+            quote_id: VRSC.to_string(),
+            height: 1000,
+        };
+        let book = build_order_book(market, offers, &rates);
+
+        let asks: Vec<_> = book.asks.iter().map(|e| e.offer.txid.as_str()).collect();
+        assert_eq!(asks, vec!["ask-240", "ask-250"]);
+        let bids: Vec<_> = book.bids.iter().map(|e| e.offer.txid.as_str()).collect();
+        assert_eq!(bids, vec!["bid-dai-230", "bid-200"]);
+
+        assert_eq!(book.best_ask, Some(240.0));
+        assert!((book.best_bid.unwrap() - 230.0).abs() < 1e-9);
+        assert!((book.spread.unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(book.ask_depth, amount("0.3"));
+        assert_eq!(book.bids[1].cumulative_size, amount("0.45"));
+        assert!((book.ask_total - 73.0).abs() < 1e-9);
+        assert_eq!(book.unpriced, vec![UnpricedOffer {
+            txid: "bid-unpriced".to_string(),
+            reason: "No conversion from iUnpriced to VRSC".to_string(),
+        }]);
+    }
+}
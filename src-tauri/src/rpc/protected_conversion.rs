@@ -0,0 +1,345 @@
+// Slippage-protected Conversions
+use crate::rpc::{VerusRpcClient, RpcError, SendCurrencyRequest};
+use crate::rpc::amount::Amount;
+use crate::rpc::conversion_routes::parse_estimate;
+use crate::rpc::identity_name::IdentityRef;
+use crate::rpc::transfer_tracker::{find_export, find_import, reserve_transfers};
+use crate::rpc::tx_inspector::{addresses, output_values};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Blocks after the send to look for its import before the payout is reported unknown
+const SETTLEMENT_SEARCH_BLOCKS: u64 = 200;
+
+/// Bound on the output of a conversion, resolved to a minimum amount
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlippageLimit {
+    MinOutput(Amount),
+    /// Fraction the output may fall below the quote the user accepted, 0.01 = 1%
+    MaxSlippage { expected_output: Amount, tolerance: f64 },
+}
+
+impl SlippageLimit {
+    pub fn min_output(self) -> Result<Amount, RpcError> {
+        match self {
+            SlippageLimit::MinOutput(min) if min.is_positive() => Ok(min),
+            SlippageLimit::MaxSlippage { expected_output, tolerance }
+                if expected_output.is_positive() && (0.0..1.0).contains(&tolerance) =>
+            {
+                // Round the allowance down so the limit never exceeds the stated tolerance
+                let allowance = (expected_output.sats() as f64 * tolerance).floor() as i64;
+                Ok(Amount::from_sats(expected_output.sats() - allowance))
+            }
+            _ => Err(RpcError::ValidationError(
+                "Minimum output must be positive and slippage tolerance between 0 and 1".to_string()
+            )),
+        }
+    }
+}
+
+/// A conversion to send, as submitted by the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectedConversionRequest {
+    pub from_address: String,
+    pub source: String,
+    pub amount: Amount,
+    pub destination: String,
+    #[serde(default)]
+    pub via: Option<String>,
+    pub to_address: String,
+    /// `{"min_output": n}` or `{"max_slippage": {"expected_output": n, "tolerance": f}}`
+    pub limit: SlippageLimit,
+}
+
+/// A conversion that was sent, kept by the frontend to check its settlement later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtectedConversion {
+    pub opid: String,
+    /// Copied from a settlement once the send succeeds, so later checks do not need the opid
+    #[serde(default)]
+    pub txid: Option<String>,
+    pub source: String,
+    pub destination: String,
+    pub destination_id: String,
+    pub via: Option<String>,
+    pub amount: Amount,
+    pub to_address: String,
+    /// Estimate taken immediately before sending
+    pub estimated_output: Amount,
+    pub min_output: Amount,
+    pub sent_height: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettlementStatus {
+    /// The send operation is still being built
+    Pending,
+    /// The transfer is on chain but its conversion has not been paid out
+    Submitted,
+    Settled,
+    Failed,
+    /// The send operation or the payout could not be found
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversionSettlement {
+    pub status: SettlementStatus,
+    pub txid: Option<String>,
+    pub settlement_txid: Option<String>,
+    pub actual_output: Option<Amount>,
+    pub estimated_output: Amount,
+    pub min_output: Amount,
+    /// Fraction the actual output fell short of the estimate (negative when it beat it)
+    pub slippage: Option<f64>,
+    pub below_minimum: bool,
+    pub error: Option<String>,
+}
+
+impl ConversionSettlement {
+    fn new(conversion: &ProtectedConversion, status: SettlementStatus) -> Self {
+        Self {
+            status,
+            txid: None,
+            settlement_txid: None,
+            actual_output: None,
+            estimated_output: conversion.estimated_output,
+            min_output: conversion.min_output,
+            slippage: None,
+            below_minimum: false,
+            error: None,
+        }
+    }
+
+    fn settled(mut self, settlement_txid: String, actual_output: Amount) -> Self {
+        self.status = SettlementStatus::Settled;
+        self.settlement_txid = Some(settlement_txid);
+        self.actual_output = Some(actual_output);
+        if self.estimated_output.is_positive() {
+            self.slippage = Some(1.0 - actual_output.to_f64() / self.estimated_output.to_f64());
+        }
+        self.below_minimum = actual_output < self.min_output;
+        self
+    }
+
+    fn with_status(mut self, status: SettlementStatus, error: String) -> Self {
+        self.status = status;
+        self.error = Some(error);
+        self
+    }
+}
+
+/// Value of the destination currency an import transaction pays to `address`
+///
+/// An import that settles several conversions to the same address reports their total.
+pub fn import_payout(import: &Value, address: &str, destination_id: &str, native_id: &str) -> Option<Amount> {
+    let paid: Vec<Amount> = import.get("vout").and_then(|v| v.as_array()).into_iter().flatten()
+        .filter(|output| addresses(output).iter().any(|a| a == address))
+        .filter_map(|output| output_values(output, native_id).get(destination_id).copied())
+        .collect();
+    if paid.is_empty() {
+        return None;
+    }
+    paid.into_iter().try_fold(Amount::ZERO, |total, amount| total.checked_add(amount))
+}
+
+impl VerusRpcClient {
+    /// Re-estimate a conversion and send it only if the output is within its limit
+    pub async fn convert_currency_protected(
+        &self,
+        request: &ProtectedConversionRequest
+    ) -> Result<ProtectedConversion, RpcError> {
+        let min_output = request.limit.min_output()?;
+        let via = request.via.as_deref().filter(|v| !v.is_empty());
+        let destination_def = self.get_currency(&request.destination, None).await?;
+
+        let estimate = self.estimate_conversion(&request.source, request.amount, &request.destination, via).await?;
+        let estimated_output = parse_estimate(&estimate).ok_or(RpcError::InvalidResponse)?.output;
+        if estimated_output < min_output {
+            return Err(RpcError::ValidationError(format!(
+                "Estimated output {} {} is below the minimum of {}; the price has moved outside your tolerance",
+                estimated_output, request.destination, min_output
+            )));
+        }
+
+        let sent_height = self.get_block_count(None).await?;
+        let mut output = SendCurrencyRequest::new(&request.to_address, &request.source, request.amount)
+            .convert_to(&request.destination);
+        if let Some(via) = via {
+            output = output.via(via);
+        }
+        let opid = self.send_currency(&request.from_address, vec![output], None, None, None).await?;
+
+        Ok(ProtectedConversion {
+            opid,
+            txid: None,
+            source: request.source.clone(),
+            destination: request.destination.clone(),
+            destination_id: destination_def.currencyid,
+            via: via.map(|v| v.to_string()),
+            amount: request.amount,
+            to_address: request.to_address.clone(),
+            estimated_output,
+            min_output,
+            sent_height,
+        })
+    }
+
+    /// Address outputs to `to_address` are paid to, resolving identity names to i-addresses
+    async fn payout_address(&self, to_address: &str) -> Result<String, RpcError> {
+        match IdentityRef::parse(to_address) {
+            Ok(IdentityRef::Name(_)) => Ok(self.resolve_identity(to_address, None).await?.identityaddress),
+            Ok(IdentityRef::Address(address)) => Ok(address),
+            Err(_) => Ok(to_address.to_string()),
+        }
+    }
+
+    /// Compare a sent conversion's payout against the estimate it was sent with
+    ///
+    /// The payout is read from the import that settles the send's reserve transfer,
+    /// found through the export that bundled it.
+    pub async fn check_conversion_settlement(
+        &self,
+        conversion: &ProtectedConversion
+    ) -> Result<ConversionSettlement, RpcError> {
+        let txid = match conversion.txid.as_ref().filter(|t| !t.is_empty()) {
+            Some(txid) => txid.clone(),
+            None => {
+                let operation = self.z_get_operation_status(Some(vec![conversion.opid.clone()]), None).await?
+                    .into_iter()
+                    .next();
+                let Some(operation) = operation else {
+                    // Finished operations are dropped from the list once the daemon restarts,
+                    // so the send may well have gone through
+                    let mut result = ConversionSettlement::new(conversion, SettlementStatus::Unknown);
+                    result.error = Some(format!("Operation {} is unknown to the daemon", conversion.opid));
+                    return Ok(result);
+                };
+
+                match operation.status.as_str() {
+                    "success" => {}
+                    "failed" | "cancelled" => {
+                        let mut result = ConversionSettlement::new(conversion, SettlementStatus::Failed);
+                        result.error = operation.error
+                            .map(|e| e.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()).unwrap_or(e.to_string()));
+                        return Ok(result);
+                    }
+                    _ => return Ok(ConversionSettlement::new(conversion, SettlementStatus::Pending)),
+                }
+
+                operation.result
+                    .and_then(|r| r.get("txid").and_then(|t| t.as_str()).map(|t| t.to_string()))
+                    .ok_or(RpcError::InvalidResponse)?
+            }
+        };
+        let mut result = ConversionSettlement::new(conversion, SettlementStatus::Submitted);
+        result.txid = Some(txid.clone());
+
+        let decoded = self.get_raw_transaction(&txid, true).await?;
+        let confirmations = decoded.get("confirmations").and_then(|c| c.as_i64()).unwrap_or(0);
+        if confirmations < 0 {
+            return Ok(result.with_status(SettlementStatus::Failed, format!("Transaction {} was orphaned", txid)));
+        }
+        if confirmations == 0 {
+            return Ok(result);
+        }
+
+        let transfers = reserve_transfers(&decoded);
+        let Some(import_currency) = transfers.first()
+            .and_then(|t| t.get("destinationcurrencyid"))
+            .and_then(|c| c.as_str())
+        else {
+            return Ok(result.with_status(SettlementStatus::Unknown, format!("Transaction {} does not contain a conversion", txid)));
+        };
+
+        let sent_height = decoded.get("height").and_then(|h| h.as_u64()).unwrap_or(conversion.sent_height);
+        let exports = self.get_exports(import_currency, Some(sent_height), None).await?;
+        let import = match find_export(&exports, &transfers, sent_height) {
+            Some((export_txid, _)) => {
+                let imports = self.get_imports(import_currency, Some(sent_height), None).await?;
+                find_import(&imports, &export_txid)
+            }
+            None => None,
+        };
+        let Some((import_txid, _)) = import else {
+            let tip = self.get_block_count(None).await?;
+            if tip.saturating_sub(sent_height) > SETTLEMENT_SEARCH_BLOCKS {
+                return Ok(result.with_status(SettlementStatus::Unknown, format!(
+                    "No import of transaction {} found within {} blocks", txid, SETTLEMENT_SEARCH_BLOCKS
+                )));
+            }
+            return Ok(result);
+        };
+
+        let import = self.get_raw_transaction(&import_txid, true).await?;
+        let address = self.payout_address(&conversion.to_address).await?;
+        let native_id = self.get_info(None).await?.chainid.unwrap_or_default();
+        Ok(match import_payout(&import, &address, &conversion.destination_id, &native_id) {
+            Some(actual) => result.settled(import_txid, actual),
+            None => {
+                let error = format!("Import {} pays no {} to {}", import_txid, conversion.destination, conversion.to_address);
+                result.settlement_txid = Some(import_txid);
+                result.with_status(SettlementStatus::Unknown, error)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{amount, VRSC, DAI};
+    use serde_json::json;
+
+    fn conversion() -> ProtectedConversion {
+        ProtectedConversion {
+            opid: "opid-1".to_string(),
+            txid: None,
+            source: "VRSC".to_string(),
+            destination: "DAI.vETH".to_string(),
+            destination_id: DAI.to_string(),
+            via: Some("Bridge.vETH".to_string()),
+            amount: amount("10"),
+            to_address: "alice@".to_string(),
+            estimated_output: amount("30"),
+            min_output: amount("29.7"),
+            sent_height: 100,
+        }
+    }
+
+    #[test]
+    fn test_slippage_limit() {
+        let limit = SlippageLimit::MaxSlippage { expected_output: amount("30"), tolerance: 0.01 };
+        assert_eq!(limit.min_output().unwrap(), amount("29.7"));
+        assert_eq!(SlippageLimit::MinOutput(amount("5")).min_output().unwrap(), amount("5"));
+        assert!(SlippageLimit::MaxSlippage { expected_output: amount("30"), tolerance: 1.5 }.min_output().is_err());
+        assert!(SlippageLimit::MinOutput(Amount::ZERO).min_output().is_err());
+    }
+
+    #[test]
+    fn test_import_payout() {
+        let import = json!({
+            "txid": "import",
+            "vout": [
+                { "n": 0, "value": 0.0, "valueSat": 0, "scriptPubKey": {
+                    "type": "cryptocondition", "addresses": ["iBob"], "reserveoutput": { "currencyvalues": { DAI: 5.0 } } } },
+                { "n": 1, "value": 0.0, "valueSat": 0, "scriptPubKey": {
+                    "type": "cryptocondition", "addresses": ["iAlice"], "reserveoutput": { "currencyvalues": { DAI: 29.5 } } } },
+                { "n": 2, "value": 1.5, "valueSat": 150000000, "scriptPubKey": { "type": "pubkeyhash", "addresses": ["iAlice"] } }
+            ]
+        });
+        assert_eq!(import_payout(&import, "iAlice", DAI, VRSC), Some(amount("29.5")));
+        assert_eq!(import_payout(&import, "iAlice", VRSC, VRSC), Some(amount("1.5")));
+        assert_eq!(import_payout(&import, "iCarol", DAI, VRSC), None);
+    }
+
+    #[test]
+    fn test_settlement_compares_against_estimate() {
+        let settled = ConversionSettlement::new(&conversion(), SettlementStatus::Submitted).settled("import".to_string(), amount("29.4"));
+        assert_eq!(settled.status, SettlementStatus::Settled);
+        assert!((settled.slippage.unwrap() - 0.02).abs() < 1e-9);
+        assert!(settled.below_minimum);
+    }
+}
@@ -0,0 +1,347 @@
+// Typed sendcurrency Output Builder
+use crate::rpc::{RpcError, SendCurrencyRequest};
+use crate::rpc::amount::Amount;
+use crate::rpc::currency_validation::{field_errors_result, FieldError};
+use serde::{Deserialize, Serialize};
+
+/// A `sendcurrency` output described by what it does rather than by raw flags
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SendOutput {
+    Send {
+        address: String,
+        currency: String,
+        amount: Amount,
+        #[serde(default)]
+        memo: Option<String>,
+        #[serde(default)]
+        fee_currency: Option<String>,
+    },
+    Convert {
+        address: String,
+        currency: String,
+        amount: Amount,
+        convert_to: String,
+        #[serde(default)]
+        via: Option<String>,
+        #[serde(default)]
+        preconvert: bool,
+        /// Deliver the converted currency to another system
+        #[serde(default)]
+        export_to: Option<String>,
+        #[serde(default)]
+        refund_to: Option<String>,
+        #[serde(default)]
+        add_conversion_fees: bool,
+        #[serde(default)]
+        fee_currency: Option<String>,
+    },
+    Export {
+        address: String,
+        currency: String,
+        amount: Amount,
+        export_to: String,
+        #[serde(default)]
+        refund_to: Option<String>,
+        #[serde(default)]
+        fee_currency: Option<String>,
+    },
+    ExportCurrency {
+        address: String,
+        currency: String,
+        export_to: String,
+        #[serde(default)]
+        fee_currency: Option<String>,
+    },
+    ExportId {
+        address: String,
+        export_to: String,
+        #[serde(default)]
+        fee_currency: Option<String>,
+    },
+}
+
+impl SendOutput {
+    pub fn to_request(&self) -> SendCurrencyRequest {
+        let (output, fee_currency) = match self {
+            SendOutput::Send { address, currency, amount, memo, fee_currency } => {
+                let mut output = SendCurrencyRequest::new(address, currency, *amount);
+                if let Some(memo) = memo {
+                    output = output.memo(memo);
+                }
+                (output, fee_currency)
+            }
+            SendOutput::Convert {
+                address, currency, amount, convert_to, via, preconvert, export_to, refund_to,
+                add_conversion_fees, fee_currency,
+            } => {
+                let mut output = SendCurrencyRequest::new(address, currency, *amount).convert_to(convert_to);
+                if let Some(via) = via {
+                    output = output.via(via);
+                }
+                if *preconvert {
+                    output = output.preconvert();
+                }
+                if let Some(system) = export_to {
+                    output = output.export_to(system);
+                }
+                if let Some(refund) = refund_to {
+                    output = output.refund_to(refund);
+                }
+                if *add_conversion_fees {
+                    output = output.add_conversion_fees();
+                }
+                (output, fee_currency)
+            }
+            SendOutput::Export { address, currency, amount, export_to, refund_to, fee_currency } => {
+                let mut output = SendCurrencyRequest::new(address, currency, *amount).export_to(export_to);
+                if let Some(refund) = refund_to {
+                    output = output.refund_to(refund);
+                }
+                (output, fee_currency)
+            }
+            SendOutput::ExportCurrency { address, currency, export_to, fee_currency } => {
+                let output = SendCurrencyRequest::new(address, currency, Amount::ZERO)
+                    .export_to(export_to)
+                    .export_currency();
+                (output, fee_currency)
+            }
+            SendOutput::ExportId { address, export_to, fee_currency } => {
+                (SendCurrencyRequest::identity_export(address, export_to), fee_currency)
+            }
+        };
+
+        match fee_currency {
+            Some(currency) => output.fee_currency(currency),
+            None => output,
+        }
+    }
+}
+
+impl SendCurrencyRequest {
+    /// A plain send of `amount` of `currency` to `address`
+    pub fn new(address: &str, currency: &str, amount: Amount) -> Self {
+        Self {
+            currency: Some(currency.to_string()),
+            amount,
+            address: address.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Export this chain's ID to another system; no currency or amount is sent
+    pub fn identity_export(address: &str, destination_system: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            exportto: Some(destination_system.to_string()),
+            exportid: Some(true),
+            ..Default::default()
+        }
+    }
+
+    pub fn convert_to(mut self, currency: &str) -> Self {
+        self.convertto = Some(currency.to_string());
+        self
+    }
+
+    /// Convert through a basket holding both currencies
+    pub fn via(mut self, basket: &str) -> Self {
+        self.via = Some(basket.to_string());
+        self
+    }
+
+    /// Convert at the launch price of a currency that has not launched yet
+    pub fn preconvert(mut self) -> Self {
+        self.preconvert = Some(true);
+        self
+    }
+
+    pub fn export_to(mut self, system: &str) -> Self {
+        self.exportto = Some(system.to_string());
+        self
+    }
+
+    /// Send the currency's definition along with the export
+    pub fn export_currency(mut self) -> Self {
+        self.exportcurrency = Some(true);
+        self
+    }
+
+    pub fn refund_to(mut self, address: &str) -> Self {
+        self.refundto = Some(address.to_string());
+        self
+    }
+
+    pub fn memo(mut self, memo: &str) -> Self {
+        self.memo = Some(memo.to_string());
+        self
+    }
+
+    pub fn fee_currency(mut self, currency: &str) -> Self {
+        self.feecurrency = Some(currency.to_string());
+        self
+    }
+
+    /// Add conversion fees on top of `amount` rather than taking them out of it
+    pub fn add_conversion_fees(mut self) -> Self {
+        self.addconversionfees = Some(true);
+        self
+    }
+
+    /// Combination rules the daemon enforces, reported against `outputs[index]`
+    pub fn errors(&self, index: usize) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let field = |name: &str| format!("outputs[{}].{}", index, name);
+        let set = |flag: Option<bool>| flag.unwrap_or(false);
+        let same = |a: &Option<String>, b: &Option<String>| {
+            matches!((a, b), (Some(a), Some(b)) if a.eq_ignore_ascii_case(b))
+        };
+
+        let export_currency = set(self.exportcurrency);
+        let export_id = set(self.exportid);
+
+        if self.address.trim().is_empty() {
+            errors.push(FieldError::new(field("address"), "Destination address is required"));
+        }
+        if self.currency.is_none() && !export_id {
+            errors.push(FieldError::new(field("currency"), "Currency is required"));
+        }
+        if self.amount.is_negative() {
+            errors.push(FieldError::new(field("amount"), "Amount cannot be negative"));
+        } else if self.amount.is_zero() && !export_currency && !export_id {
+            errors.push(FieldError::new(field("amount"), "Amount must be greater than zero"));
+        }
+
+        if self.convertto.is_some() && same(&self.convertto, &self.currency) {
+            errors.push(FieldError::new(field("convertto"), "Cannot convert a currency to itself"));
+        }
+        if self.via.is_some() {
+            if self.convertto.is_none() {
+                errors.push(FieldError::new(field("via"), "via requires convertto"));
+            } else if same(&self.via, &self.convertto) || same(&self.via, &self.currency) {
+                errors.push(FieldError::new(field("via"), "via must be a basket other than the source and destination"));
+            }
+        }
+        if set(self.preconvert) && self.convertto.is_none() {
+            errors.push(FieldError::new(field("preconvert"), "preconvert requires convertto"));
+        }
+        if set(self.addconversionfees) && self.convertto.is_none() {
+            errors.push(FieldError::new(field("addconversionfees"), "addconversionfees requires convertto"));
+        }
+
+        if export_currency && self.exportto.is_none() {
+            errors.push(FieldError::new(field("exportcurrency"), "exportcurrency requires exportto"));
+        }
+        if export_id && self.exportto.is_none() {
+            errors.push(FieldError::new(field("exportid"), "exportid requires exportto"));
+        }
+        if export_currency && export_id {
+            errors.push(FieldError::new(field("exportid"), "Cannot export a currency and an ID in the same output"));
+        }
+        if (export_currency || export_id) && self.convertto.is_some() {
+            errors.push(FieldError::new(field("convertto"), "Definition and ID exports cannot convert"));
+        }
+
+        if self.refundto.is_some() && self.convertto.is_none() && self.exportto.is_none() {
+            errors.push(FieldError::new(field("refundto"), "refundto only applies to conversions and exports"));
+        }
+        if self.memo.is_some() && !self.address.starts_with("zs") {
+            errors.push(FieldError::new(field("memo"), "Memos can only be sent to shielded (zs) addresses"));
+        }
+
+        errors
+    }
+}
+
+/// Check every output of a `sendcurrency` call
+pub fn validate_send_outputs(outputs: &[SendCurrencyRequest]) -> Vec<FieldError> {
+    if outputs.is_empty() {
+        return vec![FieldError::new("outputs", "At least one output is required")];
+    }
+    outputs.iter()
+        .enumerate()
+        .flat_map(|(index, output)| output.errors(index))
+        .collect()
+}
+
+/// Fail with every output error joined, the way the daemon call would be refused
+pub fn check_send_outputs(outputs: &[SendCurrencyRequest]) -> Result<(), RpcError> {
+    field_errors_result(validate_send_outputs(outputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::amount;
+    use serde_json::json;
+
+    fn fields(outputs: &[SendCurrencyRequest]) -> Vec<String> {
+        validate_send_outputs(outputs).into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn test_unset_fields_are_omitted() {
+        let output = SendCurrencyRequest::new("alice@", "VRSC", amount("1.5"))
+            .convert_to("DAI.vETH")
+            .via("Bridge.vETH");
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            json!({ "currency": "VRSC", "amount": 1.5, "address": "alice@", "convertto": "DAI.vETH", "via": "Bridge.vETH" })
+        );
+        assert!(validate_send_outputs(&[output]).is_empty());
+    }
+
+    #[test]
+    fn test_typed_outputs() {
+        let outputs: Vec<SendOutput> = serde_json::from_value(json!([
+            { "kind": "convert", "address": "alice@", "currency": "VRSC", "amount": 2.0,
+              "convert_to": "vETH", "via": "Bridge.vETH", "export_to": "vETH", "fee_currency": "vETH" },
+            { "kind": "export_id", "address": "alice@", "export_to": "vDEX" },
+        ])).unwrap();
+        let requests: Vec<_> = outputs.iter().map(SendOutput::to_request).collect();
+        assert_eq!(
+            serde_json::to_value(&requests).unwrap(),
+            json!([
+                { "currency": "VRSC", "amount": 2.0, "address": "alice@", "convertto": "vETH",
+                  "via": "Bridge.vETH", "exportto": "vETH", "feecurrency": "vETH" },
+                { "amount": 0.0, "address": "alice@", "exportto": "vDEX", "exportid": true },
+            ])
+        );
+        assert!(validate_send_outputs(&requests).is_empty());
+    }
+
+    #[test]
+    fn test_valid_shapes() {
+        let outputs = [
+            SendCurrencyRequest::new("RAddress", "VRSC", amount("1")),
+            SendCurrencyRequest::new("alice@", "VRSC", amount("10")).convert_to("NewBasket").preconvert().refund_to("alice@"),
+            SendCurrencyRequest::new("0xabc", "VRSC", amount("5")).export_to("vETH").convert_to("vETH").via("Bridge.vETH"),
+            SendCurrencyRequest::new("alice@", "MyToken", amount("0")).export_to("vDEX").export_currency(),
+            SendCurrencyRequest::identity_export("alice@", "vDEX").fee_currency("vETH"),
+            SendCurrencyRequest::new("zs1abc", "VRSC", amount("1")).memo("thanks"),
+        ];
+        assert_eq!(fields(&outputs), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_combination_rules_are_per_output() {
+        let mut both_exports = SendCurrencyRequest::new("alice@", "VRSC", amount("0")).export_currency();
+        both_exports.exportid = Some(true);
+        let outputs = [
+            SendCurrencyRequest::new("alice@", "VRSC", amount("1")),
+            SendCurrencyRequest::new("alice@", "VRSC", amount("1")).via("Bridge.vETH").preconvert(),
+            both_exports,
+            SendCurrencyRequest::new("RAddress", "VRSC", amount("0")).memo("hi"),
+        ];
+        assert_eq!(fields(&outputs), vec![
+            "outputs[1].via",
+            "outputs[1].preconvert",
+            "outputs[2].exportcurrency",
+            "outputs[2].exportid",
+            "outputs[2].exportid",
+            "outputs[3].amount",
+            "outputs[3].memo",
+        ]);
+        assert!(check_send_outputs(&[]).is_err());
+    }
+}
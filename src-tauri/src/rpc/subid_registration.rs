@@ -0,0 +1,337 @@
+// Sub-ID Registration under a Currency/Namespace Parent
+use crate::rpc::{VerusRpcClient, RpcError, IdentityRegistration, NameReservation};
+use crate::rpc::identity_name::validate_name_component;
+use crate::rpc::currency_options::CurrencyOptions;
+use crate::rpc::amount::Amount;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Referral levels the daemon assumes when a currency does not set `idreferrallevels`
+const DEFAULT_ID_REFERRAL_LEVELS: u32 = 3;
+
+/// Registration terms of a parent currency as reported by `getcurrency`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubIdParentTerms {
+    pub currencyid: String,
+    pub name: String,
+    pub fullyqualifiedname: String,
+    pub options: CurrencyOptions,
+    pub idregistrationfees: Amount,
+    pub idreferrallevels: u32,
+}
+
+impl SubIdParentTerms {
+    pub fn from_currency(currency: &Value) -> Result<Self, RpcError> {
+        let currencyid = currency.get("currencyid")
+            .and_then(|v| v.as_str())
+            .ok_or(RpcError::InvalidResponse)?
+            .to_string();
+        let name = currency.get("name")
+            .and_then(|v| v.as_str())
+            .ok_or(RpcError::InvalidResponse)?
+            .to_string();
+        let fullyqualifiedname = currency.get("fullyqualifiedname")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| name.clone());
+
+        Ok(Self {
+            currencyid,
+            name,
+            fullyqualifiedname,
+            options: CurrencyOptions::from_bits(currency.get("options").and_then(|v| v.as_u64()).unwrap_or(0) as u32),
+            idregistrationfees: currency.get("idregistrationfees")
+                .and_then(|v| Amount::deserialize(v).ok())
+                .unwrap_or(Amount::ZERO),
+            idreferrallevels: currency.get("idreferrallevels")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+                .unwrap_or(DEFAULT_ID_REFERRAL_LEVELS),
+        })
+    }
+
+    pub fn issuance_restricted(&self) -> bool {
+        self.options.contains(CurrencyOptions::ID_RESTRICTED)
+    }
+
+    pub fn referrals_enabled(&self) -> bool {
+        self.options.contains(CurrencyOptions::ID_REFERRALS)
+    }
+
+    pub fn referral_required(&self) -> bool {
+        self.options.contains(CurrencyOptions::ID_REFERRAL_REQUIRED)
+    }
+
+    /// Fee the registrant pays, with or without a referral
+    ///
+    /// Each referral level receives `fee / (levels + 2)`, and the registrant keeps
+    /// one share as a discount (100 VRSC with 3 levels costs 80 with a referral).
+    pub fn registration_fee(&self, with_referral: bool) -> Amount {
+        if with_referral && self.referrals_enabled() {
+            let discount = Amount::from_sats(self.idregistrationfees.sats() / (self.idreferrallevels as i64 + 2));
+            Amount::from_sats(self.idregistrationfees.sats() - discount.sats())
+        } else {
+            self.idregistrationfees
+        }
+    }
+}
+
+/// Fee and eligibility for registering a sub-ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubIdQuote {
+    pub name: String,
+    pub fullyqualifiedname: String,
+    pub parent: SubIdParentTerms,
+    pub fee_currency: String,
+    pub fee: Amount,
+    pub fee_without_referral: Amount,
+    pub referral_discount: Amount,
+    pub referrals_enabled: bool,
+    pub referral_required: bool,
+    pub issuance_restricted: bool,
+    /// Whether this wallet controls the parent identity (required for restricted issuance)
+    pub wallet_controls_parent: bool,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubIdRegistrationRequest {
+    pub name: String,
+    pub parent: String,
+    pub control_address: String,
+    pub referral: Option<String>,
+    pub primaryaddresses: Vec<String>,
+    pub minimumsignatures: Option<u32>,
+    pub revocationauthority: Option<String>,
+    pub recoveryauthority: Option<String>,
+    pub privateaddress: Option<String>,
+    pub source_of_funds: Option<String>,
+}
+
+/// A submitted name commitment, kept by the frontend until it confirms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubIdCommitment {
+    pub request: SubIdRegistrationRequest,
+    pub quote: SubIdQuote,
+    pub commitment_txid: String,
+    pub namereservation: NameReservation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubIdRegistrationResult {
+    pub quote: SubIdQuote,
+    pub commitment_txid: String,
+    pub namereservation: NameReservation,
+    pub registration: Value,
+}
+
+impl VerusRpcClient {
+    /// Look up the parent currency and compute the sub-ID registration fee
+    pub async fn quote_sub_identity(
+        &self,
+        name: &str,
+        parent: &str,
+        with_referral: bool,
+        chain: Option<&str>
+    ) -> Result<SubIdQuote, RpcError> {
+        let currency: Value = match self.call_with_chain("getcurrency", json!([parent]), chain).await {
+            Ok(currency) => currency,
+            Err(RpcError::CurrencyNotFound(_)) | Err(RpcError::InvalidAddress(_)) => {
+                return Err(RpcError::ValidationError(format!(
+                    "{} has not defined a currency, so it cannot issue sub-IDs", parent
+                )));
+            }
+            Err(e) => return Err(e),
+        };
+        let terms = SubIdParentTerms::from_currency(&currency)?;
+
+        let mut errors = Vec::new();
+        if let Err(e) = validate_name_component(name) {
+            errors.push(e);
+        }
+        if terms.referral_required() && !with_referral {
+            errors.push(format!("{} requires a referral to register sub-IDs", terms.name));
+        }
+
+        let parent_identity = self.get_identity(&terms.currencyid, None, None, None, chain).await?;
+        let wallet_controls_parent = parent_identity.get("cansignfor").and_then(|v| v.as_bool()).unwrap_or(false)
+            || parent_identity.get("canspendfor").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if terms.issuance_restricted() && !wallet_controls_parent {
+            errors.push(format!("Only the controller of {} can register its sub-IDs", terms.name));
+        }
+
+        let fee = terms.registration_fee(with_referral);
+        let fee_without_referral = terms.registration_fee(false);
+
+        Ok(SubIdQuote {
+            name: name.to_string(),
+            fullyqualifiedname: format!("{}.{}@", name, terms.fullyqualifiedname),
+            fee_currency: terms.name.clone(),
+            fee,
+            fee_without_referral,
+            referral_discount: Amount::from_sats(fee_without_referral.sats() - fee.sats()),
+            referrals_enabled: terms.referrals_enabled(),
+            referral_required: terms.referral_required(),
+            issuance_restricted: terms.issuance_restricted(),
+            wallet_controls_parent,
+            errors,
+            parent: terms,
+        })
+    }
+
+    /// Validate a sub-ID and submit its name commitment
+    ///
+    /// Pass the result to `complete_sub_identity` once the commitment has confirmed.
+    pub async fn commit_sub_identity(
+        &self,
+        request: &SubIdRegistrationRequest,
+        chain: Option<&str>
+    ) -> Result<SubIdCommitment, RpcError> {
+        let referral = request.referral.as_deref().filter(|r| !r.is_empty());
+        let quote = self.quote_sub_identity(&request.name, &request.parent, referral.is_some(), chain).await?;
+
+        if !quote.errors.is_empty() {
+            return Err(RpcError::ValidationError(quote.errors.join("; ")));
+        }
+        if request.primaryaddresses.is_empty() {
+            return Err(RpcError::ValidationError("At least one primary address is required".to_string()));
+        }
+
+        let commitment = self.register_name_commitment(
+            &request.name,
+            &request.control_address,
+            referral,
+            Some(&quote.parent.currencyid),
+            request.source_of_funds.as_deref()
+        ).await?;
+
+        Ok(SubIdCommitment {
+            request: request.clone(),
+            quote,
+            commitment_txid: commitment.txid,
+            namereservation: commitment.namereservation,
+        })
+    }
+
+    /// Register a sub-ID whose name commitment has confirmed, paying the fee in the parent's currency
+    ///
+    /// The parent and fee are quoted again rather than trusted from the commitment the
+    /// frontend hands back, so a stale or edited commitment cannot set either.
+    pub async fn complete_sub_identity(
+        &self,
+        commitment: &SubIdCommitment,
+        chain: Option<&str>
+    ) -> Result<SubIdRegistrationResult, RpcError> {
+        let tx = self.get_transaction(&commitment.commitment_txid, None, chain).await?;
+        let confirmations = tx.get("confirmations").and_then(|v| v.as_i64()).unwrap_or(0);
+        if confirmations < 0 {
+            return Err(RpcError::TransactionFailed(format!("Name commitment {} was orphaned", commitment.commitment_txid)));
+        }
+        if confirmations == 0 {
+            return Err(RpcError::ValidationError(format!(
+                "Name commitment {} has not confirmed yet; try again after the next block", commitment.commitment_txid
+            )));
+        }
+
+        let request = &commitment.request;
+        let referral = request.referral.as_deref().filter(|r| !r.is_empty());
+        let quote = self.quote_sub_identity(&request.name, &request.parent, referral.is_some(), chain).await?;
+
+        if !quote.errors.is_empty() {
+            return Err(RpcError::ValidationError(quote.errors.join("; ")));
+        }
+        let reserved_parent = &commitment.namereservation.parent;
+        if quote.parent.currencyid != commitment.quote.parent.currencyid
+            || (!reserved_parent.is_empty() && *reserved_parent != quote.parent.currencyid)
+        {
+            return Err(RpcError::ValidationError(format!(
+                "Name commitment {} was not made under {}", commitment.commitment_txid, quote.parent.fullyqualifiedname
+            )));
+        }
+
+        let identity = IdentityRegistration {
+            name: request.name.clone(),
+            parent: quote.parent.currencyid.clone(),
+            primaryaddresses: request.primaryaddresses.clone(),
+            minimumsignatures: request.minimumsignatures.unwrap_or(1),
+            revocationauthority: request.revocationauthority.clone(),
+            recoveryauthority: request.recoveryauthority.clone(),
+            privateaddress: request.privateaddress.clone(),
+            timelock: None,
+        };
+
+        let mut params = vec![
+            json!({
+                "txid": commitment.commitment_txid,
+                "namereservation": commitment.namereservation,
+                "identity": identity
+            }),
+            json!(false),
+            json!(quote.fee),
+        ];
+        if let Some(source) = &request.source_of_funds {
+            params.push(json!(source));
+        }
+
+        let registration: Value = self.call_with_chain("registeridentity", json!(params), chain).await?;
+
+        Ok(SubIdRegistrationResult {
+            quote,
+            commitment_txid: commitment.commitment_txid.clone(),
+            namereservation: commitment.namereservation.clone(),
+            registration,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(options: CurrencyOptions, fee: &str, levels: u32) -> SubIdParentTerms {
+        SubIdParentTerms {
+            currencyid: "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq".to_string(),
+            name: "vlotto".to_string(),
+            fullyqualifiedname: "vlotto".to_string(),
+            options,
+            idregistrationfees: fee.parse().unwrap(),
+            idreferrallevels: levels,
+        }
+    }
+
+    #[test]
+    fn test_referral_discount() {
+        let with_referrals = terms(CurrencyOptions::ID_REFERRALS, "100", 3);
+        assert_eq!(with_referrals.registration_fee(false).to_string(), "100.00000000");
+        assert_eq!(with_referrals.registration_fee(true).to_string(), "80.00000000");
+
+        // Referral supplied to a parent without referrals enabled: no discount
+        let no_referrals = terms(CurrencyOptions::default(), "100", 3);
+        assert_eq!(no_referrals.registration_fee(true).to_string(), "100.00000000");
+
+        let one_level = terms(CurrencyOptions::ID_REFERRALS, "0.3", 1);
+        assert_eq!(one_level.registration_fee(true).to_string(), "0.20000000");
+
+        // Shares that do not divide evenly round down to the satoshi
+        let uneven = terms(CurrencyOptions::ID_REFERRALS, "0.00000007", 1);
+        assert_eq!(uneven.registration_fee(true), Amount::from_sats(5));
+    }
+
+    #[test]
+    fn test_parent_terms_defaults() {
+        let parsed = SubIdParentTerms::from_currency(&json!({
+            "currencyid": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "name": "vlotto",
+            "options": CurrencyOptions::ID_RESTRICTED.union(CurrencyOptions::ID_REFERRAL_REQUIRED),
+            "idregistrationfees": 5
+        }))
+        .unwrap();
+
+        assert_eq!(parsed.idreferrallevels, DEFAULT_ID_REFERRAL_LEVELS);
+        assert_eq!(parsed.fullyqualifiedname, "vlotto");
+        assert!(parsed.issuance_restricted());
+        assert!(parsed.referral_required());
+        assert!(!parsed.referrals_enabled());
+    }
+}
@@ -0,0 +1,14 @@
+// Shared Test Fixtures
+use crate::rpc::amount::Amount;
+
+pub const VRSC: &str = "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV";
+pub const VETH: &str = "i9nwxtKuVYX4MSbeULLiK2ttVi6rUEhh4X";
+pub const TBTC: &str = "iS8TfRPfVpKo5FVfSUzfHBQxo9KuzpnqLU";
+pub const DAI: &str = "iGBs4DWztRNvNEJBt4mqHszLxfKTNHTkhM";
+pub const BRIDGE: &str = "i3f7tSctFkiPpiedY8QR5Tep9p4qDVebDx";
+pub const VDEX: &str = "iHog9UCTrn95qpUBFCZ7kKz7qWdMA8MQ6N";
+pub const ALICE: &str = "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq";
+
+pub fn amount(value: &str) -> Amount {
+    value.parse().unwrap()
+}
@@ -0,0 +1,385 @@
+// Cross-chain Transfer Tracker
+use crate::rpc::{VerusRpcClient, RpcError, ChainConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Target block time of Verus and PBaaS chains
+const BLOCK_SECONDS: u64 = 60;
+
+/// Typical source-chain blocks spent in each stage before the next one is reached
+const CONFIRM_BLOCKS: u64 = 1;
+const EXPORT_BLOCKS: u64 = 10;
+const NOTARIZATION_BLOCKS: u64 = 30;
+const IMPORT_BLOCKS: u64 = 5;
+
+/// Destination blocks searched back from the tip for the import
+const IMPORT_SEARCH_BLOCKS: u64 = 5000;
+
+/// A send to another system, as kept by the frontend after `export_currency` and friends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossChainTransfer {
+    /// Operation id returned by the send; used when the txid is not known yet
+    #[serde(default)]
+    pub opid: Option<String>,
+    /// Copied from a status once known, since the daemon forgets operations when it restarts
+    #[serde(default)]
+    pub txid: Option<String>,
+    /// System the transfer was exported to
+    pub destination_system: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStage {
+    /// The send operation is still being built
+    Pending,
+    /// In the source mempool
+    Submitted,
+    /// Mined on the source chain, waiting to be bundled into an export
+    Confirmed,
+    /// Bundled into an export, waiting for a notarization covering it
+    Exported,
+    /// The destination has confirmed a notarization past the export
+    Notarized,
+    Imported,
+    Failed,
+    /// The daemon no longer knows the send operation, so whether it went through cannot be told
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferStatus {
+    pub stage: TransferStage,
+    pub txid: Option<String>,
+    pub destination_system: String,
+    pub destination_id: Option<String>,
+    pub source_height: u64,
+    pub sent_height: Option<u64>,
+    pub export_txid: Option<String>,
+    pub export_height: Option<u64>,
+    /// Highest source block the destination has a confirmed notarization for
+    pub notarized_height: Option<u64>,
+    pub import_txid: Option<String>,
+    pub import_height: Option<u64>,
+    /// Whether a daemon for the destination was found to check the import
+    pub destination_connected: bool,
+    /// Rough source blocks until import, from typical export and notarization cadence
+    pub eta_blocks: Option<u64>,
+    pub eta_seconds: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl TransferStatus {
+    fn new(transfer: &CrossChainTransfer, stage: TransferStage, source_height: u64) -> Self {
+        Self {
+            stage,
+            txid: transfer.txid.clone(),
+            destination_system: transfer.destination_system.clone(),
+            destination_id: None,
+            source_height,
+            sent_height: None,
+            export_txid: None,
+            export_height: None,
+            notarized_height: None,
+            import_txid: None,
+            import_height: None,
+            destination_connected: false,
+            eta_blocks: None,
+            eta_seconds: None,
+            error: None,
+        }
+    }
+
+    fn failed(mut self, error: String) -> Self {
+        self.stage = TransferStage::Failed;
+        self.error = Some(error);
+        self
+    }
+
+    /// Fill in the ETA from the current stage and the block it was reached at
+    fn with_eta(mut self) -> Self {
+        let reached = match self.stage {
+            TransferStage::Confirmed => self.sent_height,
+            TransferStage::Exported => self.export_height,
+            _ => None,
+        };
+        let elapsed = reached.map(|h| self.source_height.saturating_sub(h)).unwrap_or(0);
+        self.eta_blocks = remaining_blocks(self.stage, elapsed);
+        self.eta_seconds = self.eta_blocks.map(|b| b * BLOCK_SECONDS);
+        self
+    }
+}
+
+/// Source blocks left until import, given how long the transfer has spent in its stage
+pub fn remaining_blocks(stage: TransferStage, elapsed: u64) -> Option<u64> {
+    let ahead = |stages: &[u64]| -> u64 {
+        let (current, rest) = stages.split_first().unwrap_or((&0, &[]));
+        current.saturating_sub(elapsed).max(1) + rest.iter().sum::<u64>()
+    };
+    match stage {
+        TransferStage::Pending | TransferStage::Submitted => {
+            Some(ahead(&[CONFIRM_BLOCKS, EXPORT_BLOCKS, NOTARIZATION_BLOCKS, IMPORT_BLOCKS]))
+        }
+        TransferStage::Confirmed => Some(ahead(&[EXPORT_BLOCKS, NOTARIZATION_BLOCKS, IMPORT_BLOCKS])),
+        TransferStage::Exported => Some(ahead(&[NOTARIZATION_BLOCKS, IMPORT_BLOCKS])),
+        TransferStage::Notarized => Some(ahead(&[IMPORT_BLOCKS])),
+        TransferStage::Imported => Some(0),
+        TransferStage::Failed | TransferStage::Unknown => None,
+    }
+}
+
+/// Reserve transfers a decoded transaction sends
+pub fn reserve_transfers(decoded: &Value) -> Vec<&Value> {
+    decoded.get("vout").and_then(|v| v.as_array()).into_iter().flatten()
+        .filter_map(|output| output.get("scriptPubKey")?.get("reservetransfer"))
+        .collect()
+}
+
+/// Whether two decoded reserve transfers move the same value to the same place
+fn same_transfer(ours: &Value, theirs: &Value) -> bool {
+    ["destination", "currencyvalues", "destinationcurrencyid", "fees"]
+        .iter()
+        .all(|key| ours.get(*key) == theirs.get(*key))
+}
+
+/// The export, as `(txid, height)`, that bundles one of `transfers`
+///
+/// `getexports` does not name the transactions a transfer came from, so exports are
+/// matched by their contents and by the source blocks they cover.
+pub fn find_export(exports: &Value, transfers: &[&Value], sent_height: u64) -> Option<(String, u64)> {
+    exports.as_array().into_iter().flatten()
+        .filter(|export| {
+            let info = export.get("exportinfo");
+            let bound = |key: &str| info.and_then(|i| i.get(key)).and_then(|h| h.as_u64());
+            bound("sourceheightstart").is_none_or(|start| start <= sent_height)
+                && bound("sourceheightend").is_none_or(|end| end >= sent_height)
+        })
+        .find(|export| {
+            export.get("transfers").and_then(|t| t.as_array()).into_iter().flatten()
+                .any(|theirs| transfers.iter().any(|ours| same_transfer(ours, theirs)))
+        })
+        .and_then(|export| {
+            Some((export.get("txid")?.as_str()?.to_string(), export.get("height")?.as_u64()?))
+        })
+}
+
+/// Height of `system` covered by the last confirmed notarization in `getnotarizationdata`
+///
+/// Reads the notarization's own height when it notarizes `system`, otherwise the proof
+/// root it carries for `system`.
+pub fn confirmed_notarized_height(data: &Value, system_id: &str) -> Option<u64> {
+    let notarizations = data.get("notarizations")?.as_array()?;
+    let confirmed = data.get("lastconfirmed")?.as_u64()?;
+    let entry = notarizations.iter()
+        .find(|n| n.get("index").and_then(|i| i.as_u64()) == Some(confirmed))
+        .or_else(|| notarizations.get(confirmed as usize))?;
+    let notarization = entry.get("notarization")?;
+
+    if notarization.get("currencyid").and_then(|c| c.as_str()) == Some(system_id) {
+        return notarization.get("notarizationheight")?.as_u64();
+    }
+    notarization.get("proofroots")?.as_array()?.iter()
+        .find(|root| root.get("systemid").and_then(|s| s.as_str()) == Some(system_id))
+        .and_then(|root| root.get("height")?.as_u64())
+}
+
+/// The import, as `(txid, height)`, of the export sent in `export_txid`
+pub fn find_import(imports: &Value, export_txid: &str) -> Option<(String, Option<u64>)> {
+    imports.as_array().into_iter().flatten()
+        .find(|entry| {
+            entry.get("import").and_then(|i| i.get("exporttxid")).and_then(|t| t.as_str()) == Some(export_txid)
+        })
+        .and_then(|entry| {
+            let txid = entry.get("importtxid").or_else(|| entry.get("txid"))?.as_str()?.to_string();
+            let height = entry.get("importheight").or_else(|| entry.get("height")).and_then(|h| h.as_u64());
+            Some((txid, height))
+        })
+}
+
+/// A daemon among `chains` that runs `system_id`, if one is reachable
+pub async fn destination_client(chains: &[ChainConfig], system_id: &str) -> Option<VerusRpcClient> {
+    for chain in chains.iter().filter(|c| c.is_active) {
+        let Ok(client) = VerusRpcClient::new(chain.credentials.clone()) else { continue };
+        let chainid = client.get_info(None).await.ok().and_then(|info| info.chainid);
+        if chainid.as_deref() == Some(system_id) {
+            return Some(client);
+        }
+    }
+    None
+}
+
+impl VerusRpcClient {
+    /// Txid of a finished send operation; `None` with `status` updated while there is none
+    async fn transfer_txid(&self, transfer: &CrossChainTransfer, status: &mut TransferStatus) -> Result<Option<String>, RpcError> {
+        if let Some(txid) = transfer.txid.as_ref().filter(|t| !t.is_empty()) {
+            return Ok(Some(txid.clone()));
+        }
+        let Some(opid) = transfer.opid.as_ref() else {
+            return Err(RpcError::ValidationError("Either a txid or an operation id is required".to_string()));
+        };
+        let operation = self.z_get_operation_status(Some(vec![opid.clone()]), None).await?.into_iter().next();
+        let Some(operation) = operation else {
+            // Finished operations are dropped once the daemon restarts, so the send may well have gone through
+            status.stage = TransferStage::Unknown;
+            status.error = Some(format!("Operation {} is unknown to the daemon", opid));
+            return Ok(None);
+        };
+        match operation.status.as_str() {
+            "success" => operation.result
+                .and_then(|r| r.get("txid").and_then(|t| t.as_str()).map(|t| Some(t.to_string())))
+                .ok_or(RpcError::InvalidResponse),
+            "failed" | "cancelled" => {
+                status.stage = TransferStage::Failed;
+                status.error = Some(operation.error
+                    .map(|e| e.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()).unwrap_or(e.to_string()))
+                    .unwrap_or_else(|| format!("Operation {}", operation.status)));
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Follow a cross-chain send from the source chain through notarization to its import
+    ///
+    /// The import can only be confirmed when one of `chains` is a daemon for the
+    /// destination; otherwise tracking stops at notarization.
+    pub async fn track_cross_chain_transfer(
+        &self,
+        transfer: &CrossChainTransfer,
+        chains: &[ChainConfig]
+    ) -> Result<TransferStatus, RpcError> {
+        let info = self.get_info(None).await?;
+        let source_id = info.chainid.unwrap_or_default();
+        let tip = self.get_block_count(None).await?;
+        let mut status = TransferStatus::new(transfer, TransferStage::Pending, tip);
+
+        let Some(txid) = self.transfer_txid(transfer, &mut status).await? else {
+            return Ok(status.with_eta());
+        };
+        status.txid = Some(txid.clone());
+        let destination_id = self.get_currency(&transfer.destination_system, None).await?.currencyid;
+        status.destination_id = Some(destination_id.clone());
+
+        let decoded = self.get_raw_transaction(&txid, true).await?;
+        let transfers = reserve_transfers(&decoded);
+        if transfers.is_empty() {
+            return Ok(status.failed("Transaction does not send anything to another system".to_string()));
+        }
+        let confirmations = decoded.get("confirmations").and_then(|c| c.as_i64()).unwrap_or(0);
+        if confirmations < 0 {
+            return Ok(status.failed(format!("Transaction {} was orphaned", txid)));
+        }
+        if confirmations == 0 {
+            status.stage = TransferStage::Submitted;
+            return Ok(status.with_eta());
+        }
+        let sent_height = decoded.get("height").and_then(|h| h.as_u64())
+            .unwrap_or((tip + 1).saturating_sub(confirmations as u64));
+        status.sent_height = Some(sent_height);
+        status.stage = TransferStage::Confirmed;
+
+        let exports = self.get_exports(&destination_id, Some(sent_height), None).await?;
+        let Some((export_txid, export_height)) = find_export(&exports, &transfers, sent_height) else {
+            return Ok(status.with_eta());
+        };
+        status.export_txid = Some(export_txid.clone());
+        status.export_height = Some(export_height);
+        status.stage = TransferStage::Exported;
+
+        let destination = destination_client(chains, &destination_id).await;
+        status.destination_connected = destination.is_some();
+        // The destination's own view is authoritative; the source only sees it through
+        // the proof roots of notarizations posted back to it
+        let notarizations = match &destination {
+            Some(destination) => destination.get_notarization_data(&source_id).await,
+            None => self.get_notarization_data(&destination_id).await,
+        };
+        status.notarized_height = notarizations.ok().and_then(|data| confirmed_notarized_height(&data, &source_id));
+        if status.notarized_height.is_some_and(|h| h >= export_height) {
+            status.stage = TransferStage::Notarized;
+        }
+
+        if let Some(destination) = &destination {
+            let destination_tip = destination.get_block_count(None).await?;
+            let start = destination_tip.saturating_sub(IMPORT_SEARCH_BLOCKS);
+            let imports = destination.get_imports(&source_id, Some(start), None).await?;
+            if let Some((import_txid, import_height)) = find_import(&imports, &export_txid) {
+                status.import_txid = Some(import_txid);
+                status.import_height = import_height;
+                status.stage = TransferStage::Imported;
+            }
+        }
+        Ok(status.with_eta())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{VRSC, VDEX};
+    use serde_json::json;
+
+    fn transfer(address: &str, amount: f64) -> Value {
+        json!({
+            "currencyvalues": { VRSC: amount },
+            "destination": { "type": 4, "address": address },
+            "destinationcurrencyid": VDEX,
+            "fees": 0.0002
+        })
+    }
+
+    #[test]
+    fn test_find_export() {
+        let ours = transfer("iAlice", 5.0);
+        let exports = json!([
+            { "height": 110, "txid": "other", "exportinfo": { "sourceheightstart": 100, "sourceheightend": 109 },
+              "transfers": [transfer("iBob", 5.0)] },
+            { "height": 112, "txid": "early", "exportinfo": { "sourceheightstart": 90, "sourceheightend": 99 },
+              "transfers": [transfer("iAlice", 5.0)] },
+            { "height": 115, "txid": "ours", "exportinfo": { "sourceheightstart": 100, "sourceheightend": 110 },
+              "transfers": [transfer("iBob", 1.0), transfer("iAlice", 5.0)] },
+        ]);
+        assert_eq!(find_export(&exports, &[&ours], 105), Some(("ours".to_string(), 115)));
+        assert_eq!(find_export(&exports, &[&transfer("iAlice", 6.0)], 105), None);
+    }
+
+    #[test]
+    fn test_confirmed_notarized_height() {
+        let data = json!({
+            "lastconfirmed": 1,
+            "notarizations": [
+                { "index": 0, "notarization": { "currencyid": VDEX, "notarizationheight": 500,
+                  "proofroots": [{ "systemid": VRSC, "height": 90 }] } },
+                { "index": 1, "notarization": { "currencyid": VDEX, "notarizationheight": 520,
+                  "proofroots": [{ "systemid": VDEX, "height": 520 }, { "systemid": VRSC, "height": 118 }] } },
+                { "index": 2, "notarization": { "currencyid": VDEX, "notarizationheight": 540,
+                  "proofroots": [{ "systemid": VRSC, "height": 130 }] } },
+            ]
+        });
+        assert_eq!(confirmed_notarized_height(&data, VRSC), Some(118));
+        assert_eq!(confirmed_notarized_height(&data, VDEX), Some(520));
+        assert_eq!(confirmed_notarized_height(&json!({ "notarizations": [] }), VRSC), None);
+    }
+
+    #[test]
+    fn test_find_import() {
+        let imports = json!([
+            { "importtxid": "first", "importheight": 600, "import": { "exporttxid": "earlier" } },
+            { "importtxid": "landed", "importheight": 640, "import": { "exporttxid": "ours" } },
+        ]);
+        assert_eq!(find_import(&imports, "ours"), Some(("landed".to_string(), Some(640))));
+        assert_eq!(find_import(&imports, "missing"), None);
+    }
+
+    #[test]
+    fn test_remaining_blocks() {
+        assert_eq!(remaining_blocks(TransferStage::Submitted, 0), Some(46));
+        assert_eq!(remaining_blocks(TransferStage::Confirmed, 4), Some(41));
+        // Overdue stages still count at least a block
+        assert_eq!(remaining_blocks(TransferStage::Exported, 50), Some(6));
+        assert_eq!(remaining_blocks(TransferStage::Notarized, 0), Some(5));
+        assert_eq!(remaining_blocks(TransferStage::Imported, 0), Some(0));
+        assert_eq!(remaining_blocks(TransferStage::Failed, 0), None);
+        assert_eq!(remaining_blocks(TransferStage::Unknown, 0), None);
+    }
+}
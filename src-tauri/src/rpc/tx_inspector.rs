@@ -0,0 +1,337 @@
+// Raw Transaction Inspection
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::amount::Amount;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// What an output does, from the keys the daemon adds to its decoded script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    Identity,
+    IdentityReservation,
+    ReserveTransfer,
+    CurrencyDefinition,
+    Commitment,
+    ReserveOutput,
+    Data,
+    Standard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InspectedInput {
+    pub txid: Option<String>,
+    pub vout: Option<u32>,
+    pub coinbase: bool,
+    /// Whether the previous output was found
+    pub resolved: bool,
+    pub addresses: Vec<String>,
+    /// Value of the previous output, keyed by currency i-address
+    pub values: BTreeMap<String, Amount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InspectedOutput {
+    pub n: u32,
+    pub kind: OutputKind,
+    /// Short human readable description
+    pub label: String,
+    pub addresses: Vec<String>,
+    /// Value the output locks, keyed by currency i-address
+    pub values: BTreeMap<String, Amount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionInspection {
+    pub txid: String,
+    pub version: Option<u32>,
+    pub locktime: Option<u64>,
+    pub expiryheight: Option<u64>,
+    pub inputs: Vec<InspectedInput>,
+    pub outputs: Vec<InspectedOutput>,
+    pub input_totals: BTreeMap<String, Amount>,
+    pub output_totals: BTreeMap<String, Amount>,
+    /// Native value taken out of the shielded pools, negative when the transaction shields funds
+    pub shielded_balance: Option<Amount>,
+    /// Native fee, known only when every input and the shielded balance were resolved
+    pub fee: Option<Amount>,
+    pub unresolved_inputs: usize,
+}
+
+pub(crate) fn add_value(values: &mut BTreeMap<String, Amount>, currency: &str, amount: Amount) {
+    let entry = values.entry(currency.to_string()).or_insert(Amount::ZERO);
+    *entry = entry.checked_add(amount).unwrap_or(*entry);
+}
+
+fn currency_values(values: Option<&Value>, into: &mut BTreeMap<String, Amount>) {
+    for (currency, amount) in values.and_then(|v| v.as_object()).into_iter().flatten() {
+        if let Ok(amount) = Amount::deserialize(amount) {
+            add_value(into, currency, amount);
+        }
+    }
+}
+
+/// Native and reserve value of a decoded output (or a `gettxout` result)
+pub fn output_values(output: &Value, native_id: &str) -> BTreeMap<String, Amount> {
+    let mut values = BTreeMap::new();
+    let native = output.get("valueSat").and_then(|v| v.as_i64()).map(Amount::from_sats)
+        .or_else(|| output.get("value").and_then(|v| Amount::deserialize(v).ok()))
+        .unwrap_or(Amount::ZERO);
+    if !native.is_zero() {
+        add_value(&mut values, native_id, native);
+    }
+
+    let script = output.get("scriptPubKey");
+    currency_values(script.and_then(|s| s.get("reserveoutput")).and_then(|r| r.get("currencyvalues")), &mut values);
+    // A transfer's fees are already part of the output's value
+    currency_values(script.and_then(|s| s.get("reservetransfer")).and_then(|t| t.get("currencyvalues")), &mut values);
+    values
+}
+
+fn script_str<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str())
+}
+
+/// Classify a decoded output and describe it
+pub fn label_output(output: &Value) -> (OutputKind, String) {
+    let script = output.get("scriptPubKey").cloned().unwrap_or(Value::Null);
+
+    if let Some(identity) = script.get("identityprimary") {
+        let name = script_str(identity, "name").unwrap_or("unknown");
+        let label = match script_str(identity, "identityaddress") {
+            Some(address) => format!("Identity {}@ ({})", name, address),
+            None => format!("Identity {}@", name),
+        };
+        return (OutputKind::Identity, label);
+    }
+    if let Some(reservation) = script.get("identityreservation").or_else(|| script.get("advancedidentityreservation")) {
+        let name = script_str(reservation, "name").unwrap_or("unknown");
+        return (OutputKind::IdentityReservation, format!("Name reservation for {}", name));
+    }
+    if let Some(transfer) = script.get("reservetransfer") {
+        let flag = |key: &str| transfer.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        let destination = script_str(transfer, "destinationcurrencyid").unwrap_or("unknown");
+        let action = if flag("preconvert") {
+            "Preconvert to"
+        } else if flag("convert") || flag("reservetoreserve") {
+            "Convert to"
+        } else {
+            "Transfer through"
+        };
+        return (OutputKind::ReserveTransfer, format!("{} {}", action, destination));
+    }
+    if let Some(definition) = script.get("currencydefinition") {
+        let name = script_str(definition, "name").unwrap_or("unknown");
+        return (OutputKind::CurrencyDefinition, format!("Currency definition {}", name));
+    }
+    if script.get("commitmenthash").is_some() {
+        return (OutputKind::Commitment, "Commitment".to_string());
+    }
+    if script.get("reserveoutput").is_some() {
+        return (OutputKind::ReserveOutput, "Currency output".to_string());
+    }
+    if script_str(&script, "type") == Some("nulldata") {
+        return (OutputKind::Data, "Data (OP_RETURN)".to_string());
+    }
+    (OutputKind::Standard, "Payment".to_string())
+}
+
+pub(crate) fn addresses(output: &Value) -> Vec<String> {
+    output.get("scriptPubKey")
+        .and_then(|s| s.get("addresses"))
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|a| a.as_str().map(|a| a.to_string()))
+        .collect()
+}
+
+/// Native value a decoded transaction moves from Sapling and Sprout into its transparent side
+///
+/// `None` when a joinsplit's public values cannot be read.
+pub fn shielded_balance(decoded: &Value) -> Option<Amount> {
+    let amount = |value: &Value, sats: &str, coins: &str| {
+        value.get(sats).and_then(|v| v.as_i64()).map(Amount::from_sats)
+            .or_else(|| value.get(coins).and_then(|v| Amount::deserialize(v).ok()))
+    };
+    let mut balance = amount(decoded, "valueBalanceZat", "valueBalance").unwrap_or(Amount::ZERO);
+    for joinsplit in decoded.get("vjoinsplit").and_then(|v| v.as_array()).into_iter().flatten() {
+        balance = balance
+            .checked_add(amount(joinsplit, "vpub_newZat", "vpub_new")?)?
+            .checked_sub(amount(joinsplit, "vpub_oldZat", "vpub_old")?)?;
+    }
+    Some(balance)
+}
+
+/// Totals, fee and output labels for a decoded transaction whose inputs are already resolved
+pub fn inspect_transaction(decoded: &Value, inputs: Vec<InspectedInput>, native_id: &str) -> TransactionInspection {
+    let outputs: Vec<InspectedOutput> = decoded.get("vout").and_then(|v| v.as_array()).into_iter().flatten()
+        .enumerate()
+        .map(|(index, output)| {
+            let (kind, label) = label_output(output);
+            InspectedOutput {
+                n: output.get("n").and_then(|n| n.as_u64()).unwrap_or(index as u64) as u32,
+                kind,
+                label,
+                addresses: addresses(output),
+                values: output_values(output, native_id),
+            }
+        })
+        .collect();
+
+    let mut input_totals = BTreeMap::new();
+    for input in &inputs {
+        for (currency, amount) in &input.values {
+            add_value(&mut input_totals, currency, *amount);
+        }
+    }
+    let mut output_totals = BTreeMap::new();
+    for output in &outputs {
+        for (currency, amount) in &output.values {
+            add_value(&mut output_totals, currency, *amount);
+        }
+    }
+
+    let unresolved_inputs = inputs.iter().filter(|i| !i.resolved && !i.coinbase).count();
+    let native = |totals: &BTreeMap<String, Amount>| totals.get(native_id).copied().unwrap_or(Amount::ZERO);
+    let coinbase = inputs.iter().any(|i| i.coinbase);
+    let shielded_balance = shielded_balance(decoded);
+    let fee = (unresolved_inputs == 0 && !coinbase)
+        .then(|| native(&input_totals).checked_add(shielded_balance?)?.checked_sub(native(&output_totals)))
+        .flatten();
+
+    TransactionInspection {
+        txid: script_str(decoded, "txid").unwrap_or_default().to_string(),
+        version: decoded.get("version").and_then(|v| v.as_u64()).map(|v| v as u32),
+        locktime: decoded.get("locktime").and_then(|v| v.as_u64()),
+        expiryheight: decoded.get("expiryheight").and_then(|v| v.as_u64()),
+        inputs,
+        outputs,
+        input_totals,
+        output_totals,
+        shielded_balance,
+        fee,
+        unresolved_inputs,
+    }
+}
+
+impl VerusRpcClient {
+    /// Previous output an input spends, unspent first and then from the transaction index
+    async fn previous_output(&self, txid: &str, vout: u32) -> Option<Value> {
+        if let Ok(Some(output)) = self.get_tx_out(txid, vout).await {
+            return Some(output);
+        }
+        let tx = self.get_raw_transaction(txid, true).await.ok()?;
+        tx.get("vout")?.as_array()?.iter()
+            .find(|o| o.get("n").and_then(|n| n.as_u64()) == Some(vout as u64))
+            .cloned()
+    }
+
+    /// Decode a serialized transaction and explain what it spends and creates
+    pub async fn inspect_raw_transaction(&self, hex: &str) -> Result<TransactionInspection, RpcError> {
+        let decoded = self.decode_raw_transaction(hex.trim()).await?;
+        let native_id = self.get_info(None).await?.chainid.unwrap_or_default();
+
+        let mut inputs = Vec::new();
+        for input in decoded.get("vin").and_then(|v| v.as_array()).into_iter().flatten() {
+            let coinbase = input.get("coinbase").is_some();
+            let txid = script_str(input, "txid").map(|t| t.to_string());
+            let vout = input.get("vout").and_then(|v| v.as_u64()).map(|v| v as u32);
+            let previous = match (&txid, vout) {
+                (Some(txid), Some(vout)) => self.previous_output(txid, vout).await,
+                _ => None,
+            };
+            inputs.push(InspectedInput {
+                coinbase,
+                resolved: previous.is_some(),
+                addresses: previous.as_ref().map(addresses).unwrap_or_default(),
+                values: previous.as_ref().map(|p| output_values(p, &native_id)).unwrap_or_default(),
+                txid,
+                vout,
+            });
+        }
+
+        Ok(inspect_transaction(&decoded, inputs, &native_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{amount, VRSC, TBTC, BRIDGE};
+    use serde_json::json;
+
+    fn input(values: &[(&str, &str)]) -> InspectedInput {
+        InspectedInput {
+            txid: Some("prev".to_string()),
+            vout: Some(0),
+            coinbase: false,
+            resolved: true,
+            addresses: vec![],
+            values: values.iter().map(|(c, a)| (c.to_string(), amount(a))).collect(),
+        }
+    }
+
+    fn decoded() -> Value {
+        json!({
+            "txid": "tx",
+            "version": 4,
+            "vin": [],
+            "vout": [
+                { "n": 0, "value": 0.0002, "valueSat": 20000, "scriptPubKey": {
+                    "type": "cryptocondition",
+                    "reservetransfer": {
+                        "version": 1, "currencyvalues": { TBTC: 0.1 }, "flags": 3,
+                        "feecurrencyid": VRSC, "fees": 0.0002, "destinationcurrencyid": BRIDGE,
+                        "destination": { "type": 2, "address": "RSender" }, "convert": true
+                    },
+                    "reqSigs": 1, "addresses": ["RTransferCondition"] } },
+                { "n": 1, "value": 9.9997, "valueSat": 999970000, "scriptPubKey": {
+                    "type": "pubkeyhash", "addresses": ["RChange"], "reserveoutput": { "currencyvalues": { TBTC: 0.4 } } } },
+                { "n": 2, "value": 0.0, "scriptPubKey": { "identityprimary": { "name": "alice", "identityaddress": "iAlice" } } }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_labels_and_totals() {
+        let inspection = inspect_transaction(&decoded(), vec![input(&[(VRSC, "10"), (TBTC, "0.5")])], VRSC);
+
+        let kinds: Vec<_> = inspection.outputs.iter().map(|o| o.kind).collect();
+        assert_eq!(kinds, vec![OutputKind::ReserveTransfer, OutputKind::ReserveOutput, OutputKind::Identity]);
+        assert_eq!(inspection.outputs[0].label, format!("Convert to {}", BRIDGE));
+        assert_eq!(inspection.outputs[2].label, "Identity alice@ (iAlice)");
+
+        assert_eq!(inspection.output_totals.get(TBTC), Some(&amount("0.5")));
+        assert_eq!(inspection.output_totals.get(VRSC), Some(&amount("9.9999")));
+        assert_eq!(inspection.fee, Some(amount("0.0001")));
+    }
+
+    #[test]
+    fn test_fee_counts_shielded_value() {
+        // Shields 5 of a 10 VRSC input
+        let shielding = json!({ "txid": "tx", "valueBalance": -5.0, "valueBalanceZat": -500000000, "vout": [
+            { "n": 0, "value": 4.9999, "valueSat": 499990000, "scriptPubKey": { "type": "pubkeyhash", "addresses": ["RChange"] } }
+        ] });
+        let inspection = inspect_transaction(&shielding, vec![input(&[(VRSC, "10")])], VRSC);
+        assert_eq!(inspection.shielded_balance, Some(amount("-5")));
+        assert_eq!(inspection.fee, Some(amount("0.0001")));
+
+        // Unshields 3 through a joinsplit with no transparent inputs
+        let unshielding = json!({ "txid": "tx", "vjoinsplit": [{ "vpub_old": 0.0, "vpub_new": 3.0 }], "vout": [
+            { "n": 0, "value": 2.9999, "valueSat": 299990000, "scriptPubKey": { "type": "pubkeyhash", "addresses": ["RBob"] } }
+        ] });
+        assert_eq!(inspect_transaction(&unshielding, Vec::new(), VRSC).fee, Some(amount("0.0001")));
+        assert_eq!(shielded_balance(&json!({ "vjoinsplit": [{ "vpub_new": 3.0 }] })), None);
+    }
+
+    #[test]
+    fn test_fee_unknown_with_unresolved_input() {
+        let mut unresolved = input(&[]);
+        unresolved.resolved = false;
+        let inspection = inspect_transaction(&decoded(), vec![input(&[(VRSC, "10")]), unresolved], VRSC);
+        assert_eq!(inspection.unresolved_inputs, 1);
+        assert_eq!(inspection.fee, None);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::rpc::amount::Amount;
+use crate::rpc::currency_options::CurrencyOptions;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -72,10 +74,10 @@ pub struct GetInfoResponse {
     pub testnet: Option<bool>,
     pub keypoololdest: Option<u64>,
     pub keypoolsize: Option<u32>,
-    pub paytxfee: Option<f64>,
+    pub paytxfee: Option<Amount>,
     pub tls_established: Option<u32>,
     pub tls_verified: Option<u32>,
-    pub relayfee: Option<f64>,
+    pub relayfee: Option<Amount>,
     pub errors: Option<String>,
     #[serde(rename = "CCid")]
     pub cc_id: Option<u32>,
@@ -94,17 +96,17 @@ pub struct GetInfoResponse {
 pub struct GetWalletInfoResponse {
     pub walletname: Option<String>,
     pub walletversion: Option<u32>,
-    pub balance: Option<f64>,
-    pub unlocked_balance: Option<f64>,
-    pub unconfirmed_balance: Option<f64>,
-    pub immature_balance: Option<f64>,
+    pub balance: Option<Amount>,
+    pub unlocked_balance: Option<Amount>,
+    pub unconfirmed_balance: Option<Amount>,
+    pub immature_balance: Option<Amount>,
     pub eligible_staking_outputs: Option<u32>,
-    pub eligible_staking_balance: Option<f64>,
-    pub reserve_balance: Option<HashMap<String, f64>>,
+    pub eligible_staking_balance: Option<Amount>,
+    pub reserve_balance: Option<HashMap<String, Amount>>,
     pub txcount: Option<u32>,
     pub keypoololdest: Option<u64>,
     pub keypoolsize: Option<u32>,
-    pub paytxfee: Option<f64>,
+    pub paytxfee: Option<Amount>,
     pub unlocked_until: Option<u64>,
     pub seedfp: Option<String>,
 }
@@ -114,10 +116,10 @@ pub struct GetMiningInfoResponse {
     pub blocks: Option<u64>,
     pub currentblocksize: Option<u64>,
     pub currentblocktx: Option<u64>,
-    pub averageblockfees: Option<f64>,
+    pub averageblockfees: Option<Amount>,
     pub difficulty: Option<f64>,
     pub networkhashps: Option<f64>,
-    pub stakingsupply: Option<f64>,
+    pub stakingsupply: Option<Amount>,
     pub errors: Option<String>,
     pub generate: Option<bool>,
     pub genproclimit: Option<u32>,
@@ -156,7 +158,7 @@ pub struct GetNetworkInfoResponse {
     pub connections: u32,
     pub networkactive: bool,
     pub networks: Vec<NetworkInfo>,
-    pub relayfee: f64,
+    pub relayfee: Amount,
     pub localaddresses: Vec<LocalAddress>,
 }
 
@@ -179,7 +181,7 @@ pub struct LocalAddress {
 #[derive(Debug, Serialize)]
 pub struct AddressInfo {
     pub address: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub account: Option<String>,
 }
 
@@ -199,8 +201,8 @@ impl<'de> Deserialize<'de> for AddressInfo {
             .ok_or_else(|| serde::de::Error::custom("Address must be a string"))?
             .to_string();
             
-        let amount = values[1].as_f64()
-            .ok_or_else(|| serde::de::Error::custom("Amount must be a number"))?;
+        let amount = Amount::deserialize(&values[1])
+            .map_err(|e| serde::de::Error::custom(format!("Invalid amount: {}", e)))?;
             
         let account = if values.len() > 2 {
             values[2].as_str().map(|s| s.to_string())
@@ -216,7 +218,7 @@ impl<'de> Deserialize<'de> for AddressInfo {
 pub type AddressGroupings = Vec<Vec<AddressInfo>>;
 
 // Currency balance response - dynamic object with currency names as keys
-pub type CurrencyBalanceResponse = HashMap<String, f64>;
+pub type CurrencyBalanceResponse = HashMap<String, Amount>;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ZTotalBalanceResponse {
@@ -237,7 +239,7 @@ pub struct OperationStatus {
 }
 
 // Identity Command Response Types
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IdentityDetails {
     pub version: u32,
     pub flags: u32,
@@ -247,7 +249,9 @@ pub struct IdentityDetails {
     pub identityaddress: String,
     pub parent: String,
     pub systemid: String,
+    #[serde(default)]
     pub contentmap: HashMap<String, String>,
+    #[serde(default)]
     pub contentmultimap: HashMap<String, serde_json::Value>,
     pub revocationauthority: String,
     pub recoveryauthority: String,
@@ -265,47 +269,69 @@ pub struct Identity {
     pub cansignfor: bool,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IdentityHistoryResponse {
+    pub fullyqualifiedname: Option<String>,
+    pub status: Option<String>,
+    pub canspendfor: Option<bool>,
+    pub cansignfor: Option<bool>,
+    pub blockheight: Option<u64>,
+    pub txid: Option<String>,
+    pub vout: Option<u32>,
+    #[serde(default)]
+    pub history: Vec<IdentityHistoryEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdentityHistoryEntry {
+    pub identity: IdentityDetails,
+    pub blockhash: Option<String>,
+    pub height: u64,
+    pub output: Option<IdentityOutputRef>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdentityOutputRef {
+    pub txid: String,
+    pub voutnum: u32,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RegisterNameCommitmentResponse {
     pub txid: String,
     pub namereservation: NameReservation,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NameReservation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
     pub name: String,
     pub salt: String,
+    #[serde(default)]
     pub referral: String,
+    #[serde(default)]
     pub parent: String,
     pub nameid: String,
 }
 
-// Marketplace Command Response Types
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Offer {
-    pub txid: String,
-    pub offer: OfferDetails,
+// VDXF Response Types
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VdxfIdResponse {
+    pub vdxfid: String,
+    pub indexid: Option<String>,
+    pub hash160result: Option<String>,
+    pub qualifiedname: Option<QualifiedName>,
+    pub bounddata: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct OfferDetails {
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QualifiedName {
     pub name: String,
-    pub offer: OfferTerms,
-    pub blockexpiry: u64,
-    pub expired: bool,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct OfferTerms {
-    pub currency: String,
-    pub amount: f64,
-    pub identity: String,
-    pub name: String,
-    pub parent: String,
-    pub originalcurrency: String,
-    pub originalamount: f64,
+    pub namespace: String,
 }
 
+// Marketplace Command Response Types
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MakeOfferResponse {
     pub txid: String,
@@ -322,36 +348,6 @@ pub struct TakeOfferResponse {
 }
 
 // Currency Command Response Types
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Currency {
-    pub name: String,
-    pub currencyid: String,
-    pub parent: String,
-    pub systemid: String,
-    pub notarizationprotocol: u32,
-    pub proofprotocol: u32,
-    pub options: u32,
-    pub nativecurrencyid: CurrencyId,
-    pub launchsystemid: String,
-    pub startblock: u64,
-    pub endblock: Option<u64>,
-    pub currencies: Vec<String>,
-    pub weights: Vec<f64>,
-    pub reserves: Vec<f64>,
-    pub conversions: Vec<f64>,
-    pub initialsupply: f64,
-    pub prelaunch: Option<bool>,
-    pub minpreconversion: Vec<f64>,
-    pub maxpreconversion: Vec<f64>,
-    pub initialcontributions: Vec<f64>,
-    pub preconversions: Vec<f64>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct CurrencyId {
-    pub hex: String,
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DefineCurrencyResponse {
     pub hex: String,
@@ -359,9 +355,9 @@ pub struct DefineCurrencyResponse {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct EstimateConversionResponse {
-    pub estimatedcurrencyout: f64,
-    pub estimatedcurrencyin: f64,
-    pub estimatedconversionfee: f64,
+    pub estimatedcurrencyout: Amount,
+    pub estimatedcurrencyin: Amount,
+    pub estimatedconversionfee: Amount,
     pub estimatedpriceinreserve: f64,
     pub estimatedpriceincurrency: f64,
 }
@@ -380,9 +376,13 @@ pub struct IdentityRegistration {
     pub parent: String,
     pub primaryaddresses: Vec<String>,
     pub minimumsignatures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub revocationauthority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub recoveryauthority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub privateaddress: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timelock: Option<u64>,
 }
 
@@ -399,27 +399,40 @@ pub struct IdentityUpdate {
     pub contentmultimap: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One output of `sendcurrency`; build with `SendCurrencyRequest::new` and the
+/// chained setters in `send_builder`, unset fields are left out of the request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct SendCurrencyRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub currency: Option<String>,
-    pub amount: f64,
+    pub amount: Amount,
     pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub convertto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub via: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub exportto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub exportcurrency: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub exportid: Option<bool>,
-    pub preconvert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preconvert: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refundto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub memo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub feecurrency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub addconversionfees: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CurrencyDefinition {
+pub struct DefineCurrencyRequest {
     pub name: String,
-    pub options: u32,
+    pub options: CurrencyOptions,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proofprotocol: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -429,27 +442,27 @@ pub struct CurrencyDefinition {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub weights: Option<Vec<f64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub reserves: Option<Vec<f64>>,
+    pub reserves: Option<Vec<Amount>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conversions: Option<Vec<f64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub initialsupply: Option<f64>,
+    pub initialsupply: Option<Amount>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub minpreconversion: Option<Vec<f64>>,
+    pub minpreconversion: Option<Vec<Amount>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub maxpreconversion: Option<Vec<f64>>,
+    pub maxpreconversion: Option<Vec<Amount>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub initialcontributions: Option<Vec<f64>>,
+    pub initialcontributions: Option<Vec<Amount>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub startblock: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endblock: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub idregistrationfees: Option<f64>,
+    pub idregistrationfees: Option<Amount>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub idreferrallevels: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub idimportfees: Option<f64>,
+    pub idimportfees: Option<Amount>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preallocations: Option<Vec<serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
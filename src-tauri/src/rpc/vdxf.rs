@@ -0,0 +1,378 @@
+// VDXF ContentMultimap Builder and Codec
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::identity_name::is_i_address;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// i-address of `vrsc::data.type.object.datadescriptor`
+pub const DATA_DESCRIPTOR_KEY: &str = "i4GC1YGEVD21afWudGoFJVdnfjJ5XWnCQv";
+
+// DataDescriptor flag bits (matches CDataDescriptor in the daemon)
+pub const FLAG_ENCRYPTED_DATA: u32 = 0x01;
+pub const FLAG_SALT_PRESENT: u32 = 0x02;
+pub const FLAG_ENCRYPTION_PUBLIC_KEY_PRESENT: u32 = 0x04;
+pub const FLAG_INCOMING_VIEWING_KEY_PRESENT: u32 = 0x08;
+pub const FLAG_SYMMETRIC_ENCRYPTION_KEY_PRESENT: u32 = 0x10;
+pub const FLAG_LABEL_PRESENT: u32 = 0x20;
+pub const FLAG_MIME_TYPE_PRESENT: u32 = 0x40;
+
+const DATA_DESCRIPTOR_VERSION: u32 = 1;
+
+/// Payload of a DataDescriptor
+///
+/// The daemon accepts `{"message": "..."}` for text and returns it the same way;
+/// binary payloads come back as a plain hex string.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ObjectData {
+    Message { message: String },
+    Hex(String),
+    Other(Value),
+}
+
+/// A `data.type.object.datadescriptor` entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataDescriptor {
+    #[serde(default = "default_descriptor_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub flags: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mimetype: Option<String>,
+    pub objectdata: ObjectData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epk: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ivk: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssk: Option<String>,
+}
+
+fn default_descriptor_version() -> u32 {
+    DATA_DESCRIPTOR_VERSION
+}
+
+impl DataDescriptor {
+    /// Create a plain text descriptor
+    pub fn message(message: &str) -> Self {
+        Self {
+            version: DATA_DESCRIPTOR_VERSION,
+            flags: 0,
+            label: None,
+            mimetype: None,
+            objectdata: ObjectData::Message { message: message.to_string() },
+            salt: None,
+            epk: None,
+            ivk: None,
+            ssk: None,
+        }
+    }
+
+    /// Create a descriptor carrying a JSON document as its message
+    pub fn json(value: &Value) -> Self {
+        Self::message(&value.to_string()).with_mimetype("application/json")
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn with_mimetype(mut self, mimetype: &str) -> Self {
+        self.mimetype = Some(mimetype.to_string());
+        self
+    }
+
+    /// Text message carried by this descriptor, if any
+    pub fn message_text(&self) -> Option<&str> {
+        match &self.objectdata {
+            ObjectData::Message { message } => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Flags implied by the optional fields that are set
+    pub fn computed_flags(&self) -> u32 {
+        let mut flags = self.flags & FLAG_ENCRYPTED_DATA;
+        if self.salt.is_some() {
+            flags |= FLAG_SALT_PRESENT;
+        }
+        if self.epk.is_some() {
+            flags |= FLAG_ENCRYPTION_PUBLIC_KEY_PRESENT;
+        }
+        if self.ivk.is_some() {
+            flags |= FLAG_INCOMING_VIEWING_KEY_PRESENT;
+        }
+        if self.ssk.is_some() {
+            flags |= FLAG_SYMMETRIC_ENCRYPTION_KEY_PRESENT;
+        }
+        if self.label.is_some() {
+            flags |= FLAG_LABEL_PRESENT;
+        }
+        if self.mimetype.is_some() {
+            flags |= FLAG_MIME_TYPE_PRESENT;
+        }
+        flags
+    }
+
+    /// Wrap the descriptor in its `{ DATA_DESCRIPTOR_KEY: {...} }` envelope
+    pub fn to_value(&self) -> Value {
+        let mut descriptor = self.clone();
+        descriptor.flags = self.computed_flags();
+        json!({ DATA_DESCRIPTOR_KEY: descriptor })
+    }
+
+    /// Unwrap a `{ DATA_DESCRIPTOR_KEY: {...} }` envelope
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let inner = value.as_object()?.get(DATA_DESCRIPTOR_KEY)?;
+        serde_json::from_value(inner.clone()).ok()
+    }
+}
+
+/// A single decoded contentmultimap value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum ContentValue {
+    DataDescriptor(DataDescriptor),
+    Raw(Value),
+}
+
+/// All values stored under one VDXF key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentMultimapEntry {
+    pub key: String,
+    pub values: Vec<ContentValue>,
+    /// Message of the first (latest) DataDescriptor, as read by the vlotto parser
+    pub latest_message: Option<String>,
+}
+
+/// Builder input from the frontend
+///
+/// `key` may be an i-address or a qualified name; a non-string `message`
+/// is stored as an `application/json` document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentMultimapInput {
+    pub key: String,
+    pub message: Value,
+    pub label: Option<String>,
+    pub mimetype: Option<String>,
+}
+
+impl ContentMultimapInput {
+    pub fn to_descriptor(&self) -> DataDescriptor {
+        let mut descriptor = match &self.message {
+            Value::String(text) => DataDescriptor::message(text),
+            other => DataDescriptor::json(other),
+        };
+        if let Some(label) = &self.label {
+            descriptor = descriptor.with_label(label);
+        }
+        if let Some(mimetype) = &self.mimetype {
+            descriptor = descriptor.with_mimetype(mimetype);
+        }
+        descriptor
+    }
+}
+
+/// Builds contentmultimap objects for `updateidentity`
+#[derive(Debug, Default)]
+pub struct ContentMultimapBuilder {
+    entries: Vec<(String, DataDescriptor)>,
+}
+
+impl ContentMultimapBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a descriptor under a key; keys may repeat and keep insertion order
+    pub fn add(mut self, key: &str, descriptor: DataDescriptor) -> Self {
+        self.entries.push((key.to_string(), descriptor));
+        self
+    }
+
+    /// Keys that still need to be resolved through `getvdxfid`
+    pub fn unresolved_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for (key, _) in &self.entries {
+            if !is_i_address(key) && !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
+
+    /// Build using a pre-resolved map of qualified name -> i-address
+    pub fn build_with_ids(
+        self,
+        resolved_ids: &HashMap<String, String>
+    ) -> Result<HashMap<String, Value>, RpcError> {
+        let mut multimap: HashMap<String, Value> = HashMap::new();
+
+        for (key, descriptor) in self.entries {
+            let vdxf_id = if is_i_address(&key) {
+                key
+            } else {
+                resolved_ids.get(&key)
+                    .cloned()
+                    .ok_or_else(|| RpcError::ValidationError(format!("Unresolved VDXF key: {}", key)))?
+            };
+
+            let slot = multimap.entry(vdxf_id).or_insert_with(|| json!([]));
+            if let Some(values) = slot.as_array_mut() {
+                values.push(descriptor.to_value());
+            }
+        }
+
+        Ok(multimap)
+    }
+
+    /// Resolve qualified names via `getvdxfid` and build the contentmultimap
+    pub async fn build(
+        self,
+        client: &VerusRpcClient,
+        chain: Option<&str>
+    ) -> Result<HashMap<String, Value>, RpcError> {
+        let mut resolved_ids = HashMap::new();
+        for key in self.unresolved_keys() {
+            let response = client.get_vdxf_id(&key, None, chain).await?;
+            resolved_ids.insert(key, response.vdxfid);
+        }
+        self.build_with_ids(&resolved_ids)
+    }
+}
+
+/// Decode a contentmultimap as returned by `getidentity`/`getidentitycontent`
+pub fn decode_content_multimap(contentmultimap: &Value) -> Result<Vec<ContentMultimapEntry>, RpcError> {
+    let map = contentmultimap.as_object()
+        .ok_or_else(|| RpcError::ValidationError("contentmultimap must be an object".to_string()))?;
+
+    let mut entries: Vec<ContentMultimapEntry> = map.iter()
+        .map(|(key, raw)| {
+            // Single values are returned bare, multiple values as an array
+            let raw_values = match raw {
+                Value::Array(items) => items.clone(),
+                other => vec![other.clone()],
+            };
+
+            let values: Vec<ContentValue> = raw_values.into_iter()
+                .map(|value| match DataDescriptor::from_value(&value) {
+                    Some(descriptor) => ContentValue::DataDescriptor(descriptor),
+                    None => ContentValue::Raw(value),
+                })
+                .collect();
+
+            let latest_message = values.iter()
+                .find_map(|value| match value {
+                    ContentValue::DataDescriptor(descriptor) => descriptor.message_text(),
+                    ContentValue::Raw(_) => None,
+                })
+                .map(|message| message.to_string());
+
+            ContentMultimapEntry { key: key.clone(), values, latest_message }
+        })
+        .collect();
+
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEDGER_KEY: &str = "iFVPmjN213NmfaiBhAkxAJWWGtcDEoXJcU";
+
+    #[test]
+    fn test_descriptor_round_trip() {
+        let descriptor = DataDescriptor::message("hello vlotto")
+            .with_label("greeting")
+            .with_mimetype("text/plain");
+
+        let encoded = descriptor.to_value();
+        assert_eq!(
+            encoded[DATA_DESCRIPTOR_KEY]["flags"],
+            json!(FLAG_LABEL_PRESENT | FLAG_MIME_TYPE_PRESENT)
+        );
+
+        let decoded = DataDescriptor::from_value(&encoded).unwrap();
+        assert_eq!(decoded.message_text(), Some("hello vlotto"));
+        assert_eq!(decoded.label.as_deref(), Some("greeting"));
+        assert_eq!(decoded.mimetype.as_deref(), Some("text/plain"));
+        assert_eq!(decoded.computed_flags(), decoded.flags);
+    }
+
+    #[test]
+    fn test_multimap_round_trip() {
+        let ledger = json!({ "ledgerVersion": 2, "currentPhase": "Drawing" });
+
+        let multimap = ContentMultimapBuilder::new()
+            .add(LEDGER_KEY, DataDescriptor::json(&ledger).with_label("ledger"))
+            .add("vlotto.ticket.note", DataDescriptor::message("first"))
+            .add("vlotto.ticket.note", DataDescriptor::message("second"))
+            .build_with_ids(&HashMap::from([
+                ("vlotto.ticket.note".to_string(), "iMzWvy5j4ciiMSBsEEVzfy66awLQ85b4GN".to_string()),
+            ]))
+            .unwrap();
+
+        let entries = decode_content_multimap(&json!(multimap)).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let ledger_entry = entries.iter().find(|e| e.key == LEDGER_KEY).unwrap();
+        match &ledger_entry.values[0] {
+            ContentValue::DataDescriptor(d) => {
+                let message: Value = serde_json::from_str(d.message_text().unwrap()).unwrap();
+                assert_eq!(message, ledger);
+                assert_eq!(d.mimetype.as_deref(), Some("application/json"));
+            }
+            other => panic!("expected data descriptor, got {:?}", other),
+        }
+
+        let notes = entries.iter().find(|e| e.key == "iMzWvy5j4ciiMSBsEEVzfy66awLQ85b4GN").unwrap();
+        let messages: Vec<_> = notes.values.iter()
+            .filter_map(|v| match v {
+                ContentValue::DataDescriptor(d) => d.message_text(),
+                ContentValue::Raw(_) => None,
+            })
+            .collect();
+        assert_eq!(messages, vec!["first", "second"]);
+        assert_eq!(notes.latest_message.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn test_unresolved_key_is_rejected() {
+        let result = ContentMultimapBuilder::new()
+            .add("vlotto.ledger.data", DataDescriptor::message("x"))
+            .build_with_ids(&HashMap::new());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_daemon_shapes() {
+        // Bare hex values and binary objectdata as returned by getidentity
+        let raw = json!({
+            LEDGER_KEY: {
+                DATA_DESCRIPTOR_KEY: { "version": 1, "flags": 0, "objectdata": "deadbeef" }
+            },
+            "iMzWvy5j4ciiMSBsEEVzfy66awLQ85b4GN": ["0a0b0c"]
+        });
+
+        let entries = decode_content_multimap(&raw).unwrap();
+        let ledger_entry = entries.iter().find(|e| e.key == LEDGER_KEY).unwrap();
+        assert_eq!(
+            ledger_entry.values[0],
+            ContentValue::DataDescriptor(DataDescriptor {
+                objectdata: ObjectData::Hex("deadbeef".to_string()),
+                ..DataDescriptor::message("")
+            })
+        );
+
+        let raw_entry = entries.iter().find(|e| e.key != LEDGER_KEY).unwrap();
+        assert_eq!(raw_entry.values[0], ContentValue::Raw(json!("0a0b0c")));
+    }
+}
@@ -0,0 +1,370 @@
+// Classified, Paginated Wallet History
+use crate::rpc::{VerusRpcClient, RpcError};
+use crate::rpc::amount::Amount;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Entries requested from `listtransactions` per round trip
+const HISTORY_BATCH: u32 = 100;
+
+/// Raw entries scanned per page, so a narrow filter cannot walk the whole wallet at once
+const MAX_SCANNED_PER_PAGE: u32 = 2000;
+
+pub const MAX_HISTORY_PAGE: u32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxCategory {
+    Send,
+    Receive,
+    Conversion,
+    IdRegistration,
+    IdUpdate,
+    Offer,
+    Stake,
+    Mint,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletTransaction {
+    pub txid: String,
+    pub category: TxCategory,
+    /// `listtransactions` category the classification started from
+    pub wallet_category: String,
+    pub address: Option<String>,
+    /// Other side of the entry: the recipient of a send, the identity of an ID
+    /// transaction or the currency a conversion goes to
+    pub counterparty: Option<String>,
+    /// Destination currency of a conversion
+    pub convert_to: Option<String>,
+    pub vout: Option<u32>,
+    /// Native currency, negative when sent
+    pub amount: Amount,
+    pub fee: Option<Amount>,
+    /// Reserve currencies carried by the same output in the decoded transaction, keyed by
+    /// currency i-address; empty when the transaction was not decoded
+    pub currencyvalues: BTreeMap<String, Amount>,
+    pub confirmations: i64,
+    pub blockhash: Option<String>,
+    pub blocktime: Option<u64>,
+    pub time: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HistoryFilter {
+    /// Only these categories; empty for all
+    #[serde(default)]
+    pub categories: Vec<TxCategory>,
+    /// Currency i-address, matching native amounts for the chain's own currency
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub min_confirmations: Option<i64>,
+    /// Unix time, compared against the block time once mined
+    #[serde(default)]
+    pub to_time: Option<u64>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, tx: &WalletTransaction, native_id: &str) -> bool {
+        if !self.categories.is_empty() && !self.categories.contains(&tx.category) {
+            return false;
+        }
+        if let Some(currency) = self.currency.as_deref() {
+            let native = currency == native_id && !tx.amount.is_zero();
+            if !native && !tx.currencyvalues.contains_key(currency) {
+                return false;
+            }
+        }
+        if self.address.is_some() && tx.address != self.address {
+            return false;
+        }
+        if self.to_time.is_some_and(|to| tx.blocktime.unwrap_or(tx.time) > to) {
+            return false;
+        }
+        self.min_confirmations.is_none_or(|min| tx.confirmations >= min)
+    }
+}
+
+/// Whether decoding an entry's transaction could change whether or how it is listed
+///
+/// Stake and mint entries are classified by their wallet category alone, and the
+/// address, confirmations and time of an entry are known before decoding.
+fn needs_decoding(entry: &WalletTransaction, filter: &HistoryFilter) -> bool {
+    let settled = |category: &TxCategory| matches!(category, TxCategory::Stake | TxCategory::Mint);
+    if settled(&entry.category) || (!filter.categories.is_empty() && filter.categories.iter().all(settled)) {
+        return false;
+    }
+    HistoryFilter { categories: Vec::new(), currency: None, ..filter.clone() }.matches(entry, "")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    /// Newest first
+    pub entries: Vec<WalletTransaction>,
+    /// Pass back as `cursor` for the next page
+    pub cursor: u32,
+    pub has_more: bool,
+}
+
+/// Whether any output script of a decoded transaction carries one of `keys`
+fn has_script_key(decoded: &Value, keys: &[&str]) -> bool {
+    decoded.get("vout").and_then(|v| v.as_array()).into_iter().flatten()
+        .filter_map(|output| output.get("scriptPubKey"))
+        .any(|script| keys.iter().any(|key| script.get(*key).is_some()))
+}
+
+fn is_conversion(decoded: &Value) -> bool {
+    decoded.get("vout").and_then(|v| v.as_array()).into_iter().flatten()
+        .filter_map(|output| output.get("scriptPubKey")?.get("reservetransfer"))
+        .any(|transfer| {
+            ["convert", "preconvert", "reservetoreserve"].iter()
+                .any(|flag| transfer.get(*flag).and_then(|v| v.as_bool()).unwrap_or(false))
+        })
+}
+
+/// Classify a wallet entry from its `listtransactions` category and, when available,
+/// the decoded transaction
+pub fn classify_transaction(wallet_category: &str, decoded: Option<&Value>, is_offer: bool) -> TxCategory {
+    match wallet_category {
+        "stake" => return TxCategory::Stake,
+        "mint" | "generate" | "immature" | "orphan" => return TxCategory::Mint,
+        _ => {}
+    }
+    if let Some(decoded) = decoded {
+        if has_script_key(decoded, &["identityreservation", "advancedidentityreservation"]) {
+            return TxCategory::IdRegistration;
+        }
+        if has_script_key(decoded, &["identityprimary"]) {
+            return TxCategory::IdUpdate;
+        }
+        if is_conversion(decoded) {
+            return TxCategory::Conversion;
+        }
+    }
+    if is_offer {
+        return TxCategory::Offer;
+    }
+    if wallet_category == "send" { TxCategory::Send } else { TxCategory::Receive }
+}
+
+/// Destination currency of the first conversion in a decoded transaction
+fn conversion_destination(decoded: &Value) -> Option<String> {
+    decoded.get("vout")?.as_array()?.iter()
+        .filter_map(|output| output.get("scriptPubKey")?.get("reservetransfer"))
+        .find_map(|transfer| transfer.get("destinationcurrencyid")?.as_str().map(|d| d.to_string()))
+}
+
+/// Name of the identity an ID transaction registers or updates
+fn identity_name(decoded: &Value) -> Option<String> {
+    decoded.get("vout")?.as_array()?.iter()
+        .filter_map(|output| output.get("scriptPubKey")?.get("identityprimary"))
+        .find_map(|identity| identity.get("name")?.as_str().map(|n| n.to_string()))
+}
+
+/// Reserve currencies on one output of a decoded transaction
+fn output_currency_values(decoded: &Value, vout: u32) -> BTreeMap<String, Amount> {
+    let output = decoded.get("vout").and_then(|v| v.as_array())
+        .and_then(|outputs| outputs.iter().find(|o| o.get("n").and_then(|n| n.as_u64()) == Some(vout as u64)));
+    let values = output.and_then(|o| {
+        o.get("scriptPubKey")
+            .and_then(|s| s.get("reserveoutput"))
+            .and_then(|r| r.get("currencyvalues"))
+            .or_else(|| o.get("reserves"))
+    });
+    values.and_then(|v| v.as_object()).into_iter().flatten()
+        .filter_map(|(id, amount)| Some((id.clone(), Amount::deserialize(amount).ok()?)))
+        .collect()
+}
+
+/// Build a typed entry from one `listtransactions` item
+pub fn parse_history_entry(raw: &Value, decoded: Option<&Value>, offer_txids: &HashSet<String>) -> Option<WalletTransaction> {
+    let txid = raw.get("txid")?.as_str()?.to_string();
+    let wallet_category = raw.get("category").and_then(|c| c.as_str()).unwrap_or("receive").to_string();
+    let amount = |key: &str| raw.get(key).and_then(|v| Amount::deserialize(v).ok());
+    let vout = raw.get("vout").and_then(|v| v.as_u64()).map(|v| v as u32);
+
+    let sent = wallet_category == "send";
+    let currencyvalues = match (decoded, vout) {
+        (Some(decoded), Some(vout)) => output_currency_values(decoded, vout)
+            .into_iter()
+            .map(|(id, value)| (id, if sent { Amount::from_sats(-value.sats()) } else { value }))
+            .collect(),
+        _ => BTreeMap::new(),
+    };
+
+    let category = classify_transaction(&wallet_category, decoded, offer_txids.contains(&txid));
+    let address = raw.get("address").and_then(|a| a.as_str()).map(|a| a.to_string());
+    let convert_to = match category {
+        TxCategory::Conversion => decoded.and_then(conversion_destination),
+        _ => None,
+    };
+    let counterparty = match category {
+        TxCategory::Send => address.clone(),
+        TxCategory::Conversion => convert_to.clone(),
+        TxCategory::IdRegistration | TxCategory::IdUpdate => decoded.and_then(identity_name),
+        _ => None,
+    };
+
+    Some(WalletTransaction {
+        category,
+        txid,
+        wallet_category,
+        address,
+        counterparty,
+        convert_to,
+        vout,
+        amount: amount("amount").unwrap_or(Amount::ZERO),
+        fee: amount("fee"),
+        currencyvalues,
+        confirmations: raw.get("confirmations").and_then(|c| c.as_i64()).unwrap_or(0),
+        blockhash: raw.get("blockhash").and_then(|b| b.as_str()).map(|b| b.to_string()),
+        blocktime: raw.get("blocktime").and_then(|b| b.as_u64()),
+        time: raw.get("time").and_then(|t| t.as_u64()).unwrap_or(0),
+    })
+}
+
+impl VerusRpcClient {
+    /// One page of wallet history, newest first, classified and filtered
+    ///
+    /// `cursor` counts `listtransactions` entries already consumed from the newest end;
+    /// start at 0 and pass back the returned cursor to continue. Categories and reserve
+    /// amounts come from `getrawtransaction`, called only for entries that need it.
+    pub async fn get_wallet_history(
+        &self,
+        cursor: u32,
+        limit: u32,
+        filter: &HistoryFilter
+    ) -> Result<HistoryPage, RpcError> {
+        let limit = limit.clamp(1, MAX_HISTORY_PAGE) as usize;
+        let native_id = self.get_info(None).await?.chainid.unwrap_or_default();
+        // Offer transactions are only recognizable while the wallet still lists them
+        let offer_txids: HashSet<String> = match self.list_open_offers().await {
+            Ok(offers) => offers.as_array().into_iter().flatten()
+                .filter_map(|o| o.get("txid").and_then(|t| t.as_str()).map(|t| t.to_string()))
+                .collect(),
+            Err(_) => HashSet::new(),
+        };
+
+        let mut decoded: HashMap<String, Option<Value>> = HashMap::new();
+        let mut entries = Vec::new();
+        let mut position = cursor;
+        let mut has_more = true;
+
+        'scan: while entries.len() < limit && position - cursor < MAX_SCANNED_PER_PAGE {
+            let batch = self.list_transactions(None, Some(HISTORY_BATCH), Some(position), None, None).await?;
+            if batch.len() < HISTORY_BATCH as usize {
+                has_more = false;
+            }
+            if batch.is_empty() {
+                break;
+            }
+
+            // Each batch is oldest first
+            for raw in batch.iter().rev() {
+                position += 1;
+                let Some(entry) = parse_history_entry(raw, None, &offer_txids) else { continue };
+                let entry = if needs_decoding(&entry, filter) {
+                    if !decoded.contains_key(&entry.txid) {
+                        // Needs -txindex for transactions the wallet did not create
+                        let tx = self.get_raw_transaction(&entry.txid, true).await.ok();
+                        decoded.insert(entry.txid.clone(), tx);
+                    }
+                    parse_history_entry(raw, decoded.get(&entry.txid).and_then(|d| d.as_ref()), &offer_txids)
+                } else {
+                    Some(entry)
+                };
+                if let Some(entry) = entry {
+                    if filter.matches(&entry, &native_id) {
+                        entries.push(entry);
+                        if entries.len() == limit {
+                            has_more = true;
+                            break 'scan;
+                        }
+                    }
+                }
+            }
+            if !has_more {
+                break;
+            }
+        }
+
+        Ok(HistoryPage { entries, cursor: position, has_more })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::test_util::{VRSC, TBTC};
+    use serde_json::json;
+
+    fn raw(category: &str, txid: &str, amount: f64) -> Value {
+        json!({ "txid": txid, "category": category, "amount": amount, "vout": 0, "confirmations": 3, "time": 1700000000 })
+    }
+
+    fn decoded(script: Value) -> Value {
+        json!({ "vout": [{ "n": 0, "value": 0.0, "scriptPubKey": script }] })
+    }
+
+    #[test]
+    fn test_classify_transaction() {
+        let registration = decoded(json!({ "identityprimary": {}, "identityreservation": {} }));
+        let update = decoded(json!({ "identityprimary": {} }));
+        let conversion = decoded(json!({ "reservetransfer": { "convert": true, "destinationcurrencyid": TBTC } }));
+        let export = decoded(json!({ "reservetransfer": { "convert": false } }));
+
+        assert_eq!(classify_transaction("send", Some(&registration), false), TxCategory::IdRegistration);
+        assert_eq!(classify_transaction("receive", Some(&update), false), TxCategory::IdUpdate);
+        assert_eq!(classify_transaction("send", Some(&conversion), false), TxCategory::Conversion);
+        assert_eq!(classify_transaction("send", Some(&export), false), TxCategory::Send);
+        assert_eq!(classify_transaction("send", None, true), TxCategory::Offer);
+        assert_eq!(classify_transaction("generate", Some(&update), false), TxCategory::Mint);
+        assert_eq!(classify_transaction("stake", None, false), TxCategory::Stake);
+        assert_eq!(classify_transaction("receive", None, false), TxCategory::Receive);
+
+        let converted = parse_history_entry(&raw("send", "t1", -1.0), Some(&conversion), &HashSet::new()).unwrap();
+        assert_eq!(converted.convert_to.as_deref(), Some(TBTC));
+        assert_eq!(converted.counterparty.as_deref(), Some(TBTC));
+        let named = decoded(json!({ "identityprimary": { "name": "alice" } }));
+        let updated = parse_history_entry(&raw("send", "t2", 0.0), Some(&named), &HashSet::new()).unwrap();
+        assert_eq!(updated.counterparty.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_reserve_values_read_and_filtered() {
+        let tx = decoded(json!({ "reserveoutput": { "currencyvalues": { TBTC: 0.25 } } }));
+        let sent = parse_history_entry(&raw("send", "t1", -0.0001), Some(&tx), &HashSet::new()).unwrap();
+        assert_eq!(sent.currencyvalues.get(TBTC), Some(&Amount::from_sats(-25_000_000)));
+        assert_eq!(sent.amount, Amount::from_sats(-10_000));
+
+        let by_currency = HistoryFilter { currency: Some(TBTC.to_string()), ..Default::default() };
+        assert!(by_currency.matches(&sent, VRSC));
+        let received = parse_history_entry(&raw("receive", "t2", 5.0), None, &HashSet::new()).unwrap();
+        assert!(!by_currency.matches(&received, VRSC));
+        assert!(HistoryFilter { currency: Some(VRSC.to_string()), ..Default::default() }.matches(&received, VRSC));
+
+        let sends_only = HistoryFilter { categories: vec![TxCategory::Send], min_confirmations: Some(5), ..Default::default() };
+        assert!(!sends_only.matches(&sent, VRSC));
+    }
+
+    #[test]
+    fn test_decode_only_when_needed() {
+        let undecoded = parse_history_entry(&raw("send", "t1", -1.0), None, &HashSet::new()).unwrap();
+        let staked = parse_history_entry(&raw("stake", "t2", 6.0), None, &HashSet::new()).unwrap();
+        assert!(needs_decoding(&undecoded, &HistoryFilter::default()));
+        assert!(!needs_decoding(&staked, &HistoryFilter::default()));
+
+        let stakes_only = HistoryFilter { categories: vec![TxCategory::Stake, TxCategory::Mint], ..Default::default() };
+        assert!(!needs_decoding(&undecoded, &stakes_only));
+        let confirmed = HistoryFilter { min_confirmations: Some(5), ..Default::default() };
+        assert!(!needs_decoding(&undecoded, &confirmed));
+        let earlier = HistoryFilter { to_time: Some(1699999999), ..Default::default() };
+        assert!(!needs_decoding(&undecoded, &earlier));
+        let by_currency = HistoryFilter { currency: Some(TBTC.to_string()), ..Default::default() };
+        assert!(needs_decoding(&undecoded, &by_currency));
+    }
+}